@@ -0,0 +1,176 @@
+use time;
+
+/// An HTTP cookie, as sent by a server in a Set-Cookie header or
+/// as held by curl's cookie engine
+#[deriving(Show, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    /// Expiry as seconds since the epoch, None for a session cookie
+    pub expires: Option<i64>,
+    /// The Max-Age attribute in seconds, if the server sent one
+    pub max_age: Option<i64>,
+    pub secure: bool,
+    pub http_only: bool
+}
+
+impl Cookie {
+    /// Create a session cookie with no attributes
+    /// # Arguments
+    /// * `name` -  the cookie name
+    /// * `value` - the cookie value
+    pub fn new(name: &str, value: &str) -> Cookie {
+        Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain: None,
+            path: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false
+        }
+    }
+
+    /// Parse the value of a Set-Cookie header. Unknown attributes are ignored.
+    /// # Arguments
+    /// * `header` -    the header value, without the "Set-Cookie:" name
+    /// # Example
+    /// ~~~ {.rust}
+    /// let c = Cookie::parse("sid=abc; Path=/; Secure; HttpOnly").unwrap();
+    /// assert!(c.secure && c.http_only);
+    /// ~~~
+    pub fn parse(header: &str) -> Option<Cookie> {
+        let mut parts = header.split(';');
+
+        let mut cookie = match parts.next() {
+            Some(pair) => match pair.find('=') {
+                Some(eq) if pair.slice_to(eq).trim().len() > 0 => {
+                    Cookie::new(pair.slice_to(eq).trim(), pair.slice_from(eq + 1).trim())
+                }
+                _ => { return None; }
+            },
+            None => { return None; }
+        };
+
+        for attr in parts {
+            let (key, value) = match attr.find('=') {
+                Some(eq) => (attr.slice_to(eq).trim(), attr.slice_from(eq + 1).trim()),
+                None => (attr.trim(), "")
+            };
+
+            match key.to_ascii_lower().as_slice() {
+                "domain" => {
+                    // a leading dot is ignored by RFC 6265
+                    let domain = match value.starts_with(".") {
+                        true => value.slice_from(1),
+                        false => value
+                    };
+                    cookie.domain = Some(domain.to_string());
+                }
+                "path" => { cookie.path = Some(value.to_string()); }
+                "expires" => { cookie.expires = parse_http_date(value); }
+                "max-age" => { cookie.max_age = from_str::<i64>(value); }
+                "secure" => { cookie.secure = true; }
+                "httponly" => { cookie.http_only = true; }
+                _ => { ; }
+            }
+        }
+
+        Some(cookie)
+    }
+
+    /// Parse a line of curl's cookie list, which uses the tab separated
+    /// Netscape cookie file format
+    /// # Arguments
+    /// * `line` -  a line as returned by CURLINFO_COOKIELIST
+    pub fn parse_netscape(line: &str) -> Option<Cookie> {
+        let (http_only, line) = match line.starts_with("#HttpOnly_") {
+            true => (true, line.slice_from("#HttpOnly_".len())),
+            false => (false, line)
+        };
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return None;
+        }
+
+        let domain = fields.get(0).trim_left_chars('.');
+        let expires = match from_str::<i64>(*fields.get(4)) {
+            Some(0) | None => None,
+            Some(t) => Some(t)
+        };
+
+        Some(Cookie {
+            name: fields.get(5).to_string(),
+            value: fields.get(6).to_string(),
+            domain: Some(domain.to_string()),
+            path: Some(fields.get(2).to_string()),
+            expires: expires,
+            max_age: None,
+            secure: *fields.get(3) == "TRUE",
+            http_only: http_only
+        })
+    }
+}
+
+/// Parse an HTTP date such as "Wed, 21 Oct 2015 07:28:00 GMT" into seconds since the epoch
+fn parse_http_date(date: &str) -> Option<i64> {
+    let formats = ["%a, %d %b %Y %H:%M:%S GMT", "%a, %d-%b-%Y %H:%M:%S GMT"];
+
+    for format in formats.iter() {
+        match time::strptime(date, *format) {
+            Ok(tm) => { return Some(tm.to_timespec().sec); }
+            Err(_) => { ; }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_set_cookie() {
+        let c = Cookie::parse("sid=a1b2=c3; Domain=.example.com; Path=/app; \
+                               Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=3600; Secure; HttpOnly").unwrap();
+
+        assert_eq!(c.name, "sid".to_string());
+        assert_eq!(c.value, "a1b2=c3".to_string());
+        assert_eq!(c.domain, Some("example.com".to_string()));
+        assert_eq!(c.path, Some("/app".to_string()));
+        assert_eq!(c.expires, Some(1445412480));
+        assert_eq!(c.max_age, Some(3600));
+        assert!(c.secure);
+        assert!(c.http_only);
+    }
+
+    #[test]
+    fn test_parse_session_cookie() {
+        let c = Cookie::parse("lang=en").unwrap();
+
+        assert_eq!(c, Cookie::new("lang", "en"));
+        assert!(Cookie::parse("no_equals_sign").is_none());
+        assert!(Cookie::parse("=value").is_none());
+    }
+
+    #[test]
+    fn test_parse_netscape() {
+        let c = Cookie::parse_netscape("#HttpOnly_.example.com\tTRUE\t/\tTRUE\t1445412480\tsid\tabc").unwrap();
+
+        assert_eq!(c.name, "sid".to_string());
+        assert_eq!(c.value, "abc".to_string());
+        assert_eq!(c.domain, Some("example.com".to_string()));
+        assert_eq!(c.path, Some("/".to_string()));
+        assert_eq!(c.expires, Some(1445412480));
+        assert!(c.secure && c.http_only);
+
+        let session = Cookie::parse_netscape("example.com\tFALSE\t/\tFALSE\t0\tlang\ten").unwrap();
+        assert_eq!(session.expires, None);
+        assert!(!session.secure && !session.http_only);
+    }
+}
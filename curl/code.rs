@@ -2,6 +2,8 @@
 /// various cURL operations
 #[deriving(PartialEq)]
 #[deriving(Eq)]
+#[deriving(Clone)]
+#[deriving(Show)]
 pub enum CURLcode {
     CURLE_OK = 0,
     CURLE_UNSUPPORTED_PROTOCOL,    /* 1 */
@@ -123,3 +125,38 @@ pub enum CURLcode {
                                             session will be queued */
     CURL_LAST /* never use! */
 }
+
+/// The codes returned by the curl_multi* functions
+#[deriving(PartialEq)]
+#[deriving(Eq)]
+#[deriving(Clone)]
+#[deriving(Show)]
+pub enum CURLMcode {
+    CURLM_CALL_MULTI_PERFORM = -1, /* please call curl_multi_perform() or
+                                      curl_multi_socket*() soon */
+    CURLM_OK = 0,
+    CURLM_BAD_HANDLE,              /* the passed-in handle is not a valid CURLM handle */
+    CURLM_BAD_EASY_HANDLE,         /* an easy handle was not good/valid */
+    CURLM_OUT_OF_MEMORY,           /* if you ever get this, you're in deep sh*t */
+    CURLM_INTERNAL_ERROR,          /* this is a libcurl bug */
+    CURLM_BAD_SOCKET,              /* the passed in socket argument did not match */
+    CURLM_UNKNOWN_OPTION,          /* curl_multi_setopt() with unsupported option */
+    CURLM_ADDED_ALREADY,           /* an easy handle already added to a multi handle was
+                                      attempted to get added - again */
+    CURLM_LAST
+}
+
+/// The codes returned by the curl_share* functions
+#[deriving(PartialEq)]
+#[deriving(Eq)]
+#[deriving(Clone)]
+#[deriving(Show)]
+pub enum CURLSHcode {
+    CURLSHE_OK,            /* all is fine */
+    CURLSHE_BAD_OPTION,    /* 1 */
+    CURLSHE_IN_USE,        /* 2 */
+    CURLSHE_INVALID,       /* 3 */
+    CURLSHE_NOMEM,         /* 4 out of memory */
+    CURLSHE_NOT_BUILT_IN,  /* 5 feature not present in lib */
+    CURLSHE_LAST           /* never use */
+}
@@ -1,43 +1,299 @@
-use libc::{c_int,c_void};
+use libc::{c_int,c_long,c_double,c_char,c_void,size_t};
+use std::ptr;
 use std::mem;
+use std::cell::RefCell;
+use std::c_str::CString;
+use sync::Arc;
 
 use curl::curl_ll::*;
-use curl::callback::{CurlCallback, SimpleCurlByteBuffer};
+use curl::callback::CurlCallback;
+use curl::error::{CurlError, CurlResult};
+use curl::handler::CurlHandlers;
+use curl::share::CurlShare;
+use curl::mime::CurlMime;
 
 pub mod opt;
 pub mod code;
 pub mod curl_ll;
 pub mod callback;
+pub mod error;
+pub mod handler;
+pub mod multi;
+pub mod share;
+pub mod mime;
 
 /// A set of options available to set on the curl 'request'. 
 /// These generally map one-to-one to the Curl options available via curl_easy_setopt.
 ///
-/// Currently, this option list is incomplete and only implements the things necessary for the HTTP examples 
-/// and a tiny bit more.
+/// Every long, string, off_t, slist and blob option has a variant. Callbacks are set
+/// through `CurlHandlers` or `easy_setopt_callback`, and CURLOPT_SHARE through `easy_share`.
 ///
 /// # Example
 /// ~~~ {.rust}
 /// let opt = Username("alice");
 /// ~~~
 pub enum EasyCurlOption<'a> {
+    // string options, copied by libcurl
+    URL(&'a str),
     Username(&'a str),
     Password(&'a str),
+    UserPwd(&'a str),
+    ProxyUserPwd(&'a str),
+    ProxyUsername(&'a str),
+    ProxyPassword(&'a str),
+    NoProxy(&'a str),
+    Range(&'a str),
+    Referer(&'a str),
+    UserAgent(&'a str),
+    CustomRequest(&'a str),
+    CookieString(&'a str),
+    CookieFile(&'a str),
+    CookieJar(&'a str),
+    CookieList(&'a str),
+    AcceptEncoding(&'a str),
+    FtpPort(&'a str),
+    FtpAccount(&'a str),
+    FtpAlternativeToUser(&'a str),
+    Interface(&'a str),
+    KrbLevel(&'a str),
+    NetrcFile(&'a str),
+    CaInfo(&'a str),
+    CaPath(&'a str),
+    CrlFile(&'a str),
+    IssuerCert(&'a str),
+    SslCert(&'a str),
+    SslCertType(&'a str),
+    SslKey(&'a str),
+    SslKeyType(&'a str),
+    KeyPasswd(&'a str),
+    SslEngine(&'a str),
+    SslCipherList(&'a str),
+    PinnedPublicKey(&'a str),
+    RandomFile(&'a str),
+    EgdSocket(&'a str),
+    SshPublicKeyFile(&'a str),
+    SshPrivateKeyFile(&'a str),
+    SshHostPublicKeyMd5(&'a str),
+    SshKnownHosts(&'a str),
+    Socks5GssapiService(&'a str),
+    MailFrom(&'a str),
+    MailAuth(&'a str),
+    RtspSessionId(&'a str),
+    RtspStreamUri(&'a str),
+    RtspTransport(&'a str),
+    TlsAuthUsername(&'a str),
+    TlsAuthPassword(&'a str),
+    TlsAuthType(&'a str),
+    DnsServers(&'a str),
     Proxy(&'a str, Option<&'a str>, Option<&'a str>),
-    URL(&'a str),
 
-    Referer(&'a str),
+    // slist options, one string per entry
+    HttpHeader(&'a [&'a str]),
+    Http200Aliases(&'a [&'a str]),
+    Quote(&'a [&'a str]),
+    PreQuote(&'a [&'a str]),
+    PostQuote(&'a [&'a str]),
+    TelnetOptions(&'a [&'a str]),
+    MailRcpt(&'a [&'a str]),
+    Resolve(&'a [&'a str]),
     UnsafeStringList(opt::CURLoption, *curl_slist),
 
+    // blob options, copied by libcurl
+    SslCertBlob(&'a [u8]),
+    SslKeyBlob(&'a [u8]),
+    IssuerCertBlob(&'a [u8]),
+    CaInfoBlob(&'a [u8]),
+    ProxySslCertBlob(&'a [u8]),
+    ProxySslKeyBlob(&'a [u8]),
+    ProxyIssuerCertBlob(&'a [u8]),
+    ProxyCaInfoBlob(&'a [u8]),
+    /// Request body for a POST, sent as-is and copied by libcurl
+    PostFields(&'a [u8]),
+
+    // long options
+    Port(int),
+    ProxyPort(int),
+    ProxyType(int),
     Timeout(int),
+    TimeoutMs(int),
+    ConnectTimeout(int),
+    ConnectTimeoutMs(int),
+    AcceptTimeoutMs(int),
+    LowSpeedLimit(int),
+    LowSpeedTime(int),
+    ResumeFrom(int),
+    PostFieldSize(int),
+    InFileSize(int),
+    MaxFileSize(int),
+    MaxRedirects(int),
+    PostRedir(int),
+    MaxConnects(int),
+    BufferSize(int),
+    DnsCacheTimeout(int),
+    IpResolve(int),
+    LocalPort(int),
+    LocalPortRange(int),
+    AddressScope(int),
+    HttpVersion(int),
+    HttpAuth(int),
+    ProxyAuth(int),
+    Netrc(int),
+    SslVersion(int),
+    SslOptions(int),
+    UseSsl(int),
+    TimeCondition(int),
+    TimeValue(int),
+    Protocols(int),
+    RedirProtocols(int),
+    FtpCreateMissingDirs(int),
+    FtpResponseTimeout(int),
+    FtpSslAuth(int),
+    FtpSslCcc(int),
+    FtpFileMethod(int),
+    SshAuthTypes(int),
+    NewFilePerms(int),
+    NewDirectoryPerms(int),
+    TftpBlkSize(int),
+    RtspRequest(int),
+    RtspClientCseq(int),
+    RtspServerCseq(int),
+    GssapiDelegation(int),
+    TcpKeepIdle(int),
+    TcpKeepIntvl(int),
+
+    // off_t options
+    PostFieldSizeLarge(i64),
+    InFileSizeLarge(i64),
+    ResumeFromLarge(i64),
+    MaxFileSizeLarge(i64),
+    MaxSendSpeedLarge(i64),
+    MaxRecvSpeedLarge(i64),
+
+    // boolean options
     VerboseMode(bool),
     ShowHeaders(bool),
-    FollowLocation(bool)
+    NoProgress(bool),
+    NoSignal(bool),
+    FailOnError(bool),
+    FollowLocation(bool),
+    AutoReferer(bool),
+    UnrestrictedAuth(bool),
+    HttpGet(bool),
+    NoBody(bool),
+    Post(bool),
+    Put(bool),
+    Upload(bool),
+    Crlf(bool),
+    TransferText(bool),
+    Append(bool),
+    DirListOnly(bool),
+    FileTime(bool),
+    HttpProxyTunnel(bool),
+    ProxyTransferMode(bool),
+    FreshConnect(bool),
+    ForbidReuse(bool),
+    ConnectOnly(bool),
+    TcpNoDelay(bool),
+    TcpKeepAlive(bool),
+    CookieSession(bool),
+    IgnoreContentLength(bool),
+    HttpTransferDecoding(bool),
+    HttpContentDecoding(bool),
+    TransferEncoding(bool),
+    SslVerifyPeer(bool),
+    SslEngineDefault(bool),
+    SslSessionIdCache(bool),
+    CertInfo(bool),
+    FtpUseEpsv(bool),
+    FtpUseEprt(bool),
+    FtpUsePret(bool),
+    FtpSkipPasvIp(bool),
+    WildcardMatch(bool),
+    Socks5GssapiNec(bool),
+    SaslIr(bool),
+    SslVerifyHost(bool)
+}
+
+/// A piece of transfer information returned by `Curl::easy_getinfo`.
+/// The variant is chosen by the type tag of the requested CURLINFO
+/// (STRING, LONG, DOUBLE or SLIST), except for CURLINFO_CERTINFO which
+/// gives a list of lines per certificate.
+#[deriving(Show, Clone, PartialEq)]
+pub enum CurlInfo {
+    InfoString(String),
+    InfoLong(i64),
+    InfoDouble(f64),
+    InfoList(Vec<String>),
+    InfoCertChain(Vec<Vec<String>>)
+}
+
+impl CurlInfo {
+    /// Returns the string value, if this is a string info
+    pub fn as_str<'a>(&'a self) -> Option<&'a str> {
+        match *self {
+            InfoString(ref s) => Some(s.as_slice()),
+            _ => None
+        }
+    }
+
+    /// Returns the integer value, if this is a long info
+    pub fn as_long(&self) -> Option<i64> {
+        match *self {
+            InfoLong(l) => Some(l),
+            _ => None
+        }
+    }
+
+    /// Returns the floating point value, if this is a double info
+    pub fn as_double(&self) -> Option<f64> {
+        match *self {
+            InfoDouble(d) => Some(d),
+            _ => None
+        }
+    }
+
+    /// Returns the list of strings, if this is a slist info
+    pub fn as_list<'a>(&'a self) -> Option<&'a [String]> {
+        match *self {
+            InfoList(ref l) => Some(l.as_slice()),
+            _ => None
+        }
+    }
+
+    /// Returns the "Name:value" lines of each certificate, if this is CURLINFO_CERTINFO
+    pub fn as_cert_chain<'a>(&'a self) -> Option<&'a [Vec<String>]> {
+        match *self {
+            InfoCertChain(ref c) => Some(c.as_slice()),
+            _ => None
+        }
+    }
 }
 
 /// This is a an opaque wrapper over the equally opaque
 /// CURL pointer.
 pub struct Curl {
-    curl: *CURL
+    curl: *CURL,
+    errbuf: Vec<c_char>,
+    share: Option<Arc<CurlShare>>,
+    // values given to libcurl by pointer, kept until their option is replaced,
+    // or the handle is reset or dropped
+    owned: RefCell<Vec<(opt::CURLoption, OwnedValue)>>
+}
+
+/// A string, list or form handed to libcurl by pointer
+enum OwnedValue {
+    OwnedString(CString),
+    OwnedList(*curl_slist),
+    OwnedMime(CurlMime)
+}
+
+impl Drop for OwnedValue {
+    fn drop(&mut self) {
+        match *self {
+            OwnedList(list) => unsafe { curl_slist_free_all(list); },
+            OwnedString(_) | OwnedMime(_) => { ; }
+        }
+    }
 }
 
 impl Curl {
@@ -47,9 +303,12 @@ impl Curl {
     /// let curl = Curl::new();
     /// ~~~
     pub fn new() -> Curl {
-        unsafe {
-            Curl {curl: curl_easy_init()}
-        }
+        let curl = unsafe {
+            Curl {curl: curl_easy_init(), errbuf: Vec::from_elem(CURL_ERROR_SIZE, 0 as c_char), share: None,
+                  owned: RefCell::new(vec![])}
+        };
+        curl.set_errbuf();
+        curl
     }
 
     /// URL-escape a string
@@ -66,15 +325,14 @@ impl Curl {
     pub fn easy_escape(&self, url: &str) -> String {
         use std::str::raw::from_c_str;
 
+        // passing the length lets NUL bytes through, escaped
         let len = url.len() as c_int;
-        url.with_c_str(|s| {
-            unsafe {
-                let raw = curl_easy_escape(self.curl,s,len);
-                let ret = from_c_str(raw);
-                curl_free(raw);
-                ret
-            }
-        })
+        unsafe {
+            let raw = curl_easy_escape(self.curl, url.as_ptr() as *c_char, len);
+            let ret = from_c_str(raw);
+            curl_free(raw);
+            ret
+        }
     }
 
     /// un-URL-escape a string
@@ -91,16 +349,14 @@ impl Curl {
     pub fn easy_unescape(&self, s: &str) -> String {
         use std::str::raw::from_buf_len;
 
-        s.with_c_str(|c_str| {
-            unsafe {
-                let in_len = s.len() as c_int;
-                let out_len = &(0 as c_int);
-                let raw = curl_easy_unescape(self.curl, c_str, in_len, out_len);
-                let ret = from_buf_len(raw as *u8,*out_len as uint);
-                curl_free(raw);
-                ret
-            }
-        })
+        unsafe {
+            let in_len = s.len() as c_int;
+            let out_len = &(0 as c_int);
+            let raw = curl_easy_unescape(self.curl, s.as_ptr() as *c_char, in_len, out_len);
+            let ret = from_buf_len(raw as *u8,*out_len as uint);
+            curl_free(raw);
+            ret
+        }
     }
 
     /// Set an option (using the easy interface). Wraps the easy_setopt function.
@@ -116,28 +372,189 @@ impl Curl {
     /// curl.easy_setopt(URL("http://google.com"));
     /// curl.easy_setopt(VerboseMode(true));
     /// ~~~
-    pub fn easy_setopt<'a>(&self, opt: EasyCurlOption<'a>) -> code::CURLcode {
+    pub fn easy_setopt<'a>(&self, opt: EasyCurlOption<'a>) -> CurlResult<()> {
         match opt {
+            AcceptEncoding(val) => self.easy_setopt_str(opt::ACCEPT_ENCODING, val),
+            AcceptTimeoutMs(val) => self.easy_setopt_long(opt::ACCEPTTIMEOUT_MS, val),
+            AddressScope(val) => self.easy_setopt_long(opt::ADDRESS_SCOPE, val),
+            Append(enable) => self.easy_setopt_bool(opt::APPEND, enable),
+            AutoReferer(enable) => self.easy_setopt_bool(opt::AUTOREFERER, enable),
+            BufferSize(val) => self.easy_setopt_long(opt::BUFFERSIZE, val),
+            CaInfo(val) => self.easy_setopt_str(opt::CAINFO, val),
+            CaInfoBlob(data) => self.easy_setopt_blob(opt::CAINFO_BLOB, data),
+            CaPath(val) => self.easy_setopt_str(opt::CAPATH, val),
+            CertInfo(enable) => self.easy_setopt_bool(opt::CERTINFO, enable),
+            ConnectOnly(enable) => self.easy_setopt_bool(opt::CONNECT_ONLY, enable),
+            ConnectTimeout(val) => self.easy_setopt_long(opt::CONNECTTIMEOUT, val),
+            ConnectTimeoutMs(val) => self.easy_setopt_long(opt::CONNECTTIMEOUT_MS, val),
+            CookieFile(val) => self.easy_setopt_str(opt::COOKIEFILE, val),
+            CookieJar(val) => self.easy_setopt_str(opt::COOKIEJAR, val),
+            CookieList(val) => self.easy_setopt_str(opt::COOKIELIST, val),
+            CookieSession(enable) => self.easy_setopt_bool(opt::COOKIESESSION, enable),
+            CookieString(val) => self.easy_setopt_str(opt::COOKIE, val),
+            Crlf(enable) => self.easy_setopt_bool(opt::CRLF, enable),
+            CrlFile(val) => self.easy_setopt_str(opt::CRLFILE, val),
+            CustomRequest(val) => self.easy_setopt_str(opt::CUSTOMREQUEST, val),
+            DirListOnly(enable) => self.easy_setopt_bool(opt::DIRLISTONLY, enable),
+            DnsCacheTimeout(val) => self.easy_setopt_long(opt::DNS_CACHE_TIMEOUT, val),
+            DnsServers(val) => self.easy_setopt_str(opt::DNS_SERVERS, val),
+            EgdSocket(val) => self.easy_setopt_str(opt::EGDSOCKET, val),
+            FailOnError(enable) => self.easy_setopt_bool(opt::FAILONERROR, enable),
+            FileTime(enable) => self.easy_setopt_bool(opt::FILETIME, enable),
             FollowLocation(enable) => self.easy_setopt_bool(opt::FOLLOWLOCATION, enable),
-            Password(pass) => self.easy_setopt_str(opt::PASSWORD, pass),
+            ForbidReuse(enable) => self.easy_setopt_bool(opt::FORBID_REUSE, enable),
+            FreshConnect(enable) => self.easy_setopt_bool(opt::FRESH_CONNECT, enable),
+            FtpAccount(val) => self.easy_setopt_str(opt::FTP_ACCOUNT, val),
+            FtpAlternativeToUser(val) => self.easy_setopt_str(opt::FTP_ALTERNATIVE_TO_USER, val),
+            FtpCreateMissingDirs(val) => self.easy_setopt_long(opt::FTP_CREATE_MISSING_DIRS, val),
+            FtpFileMethod(val) => self.easy_setopt_long(opt::FTP_FILEMETHOD, val),
+            FtpPort(val) => self.easy_setopt_str(opt::FTPPORT, val),
+            FtpResponseTimeout(val) => self.easy_setopt_long(opt::FTP_RESPONSE_TIMEOUT, val),
+            FtpSkipPasvIp(enable) => self.easy_setopt_bool(opt::FTP_SKIP_PASV_IP, enable),
+            FtpSslAuth(val) => self.easy_setopt_long(opt::FTPSSLAUTH, val),
+            FtpSslCcc(val) => self.easy_setopt_long(opt::FTP_SSL_CCC, val),
+            FtpUseEprt(enable) => self.easy_setopt_bool(opt::FTP_USE_EPRT, enable),
+            FtpUseEpsv(enable) => self.easy_setopt_bool(opt::FTP_USE_EPSV, enable),
+            FtpUsePret(enable) => self.easy_setopt_bool(opt::FTP_USE_PRET, enable),
+            GssapiDelegation(val) => self.easy_setopt_long(opt::GSSAPI_DELEGATION, val),
+            Http200Aliases(vals) => self.easy_setopt_strs(opt::HTTP200ALIASES, vals),
+            HttpAuth(val) => self.easy_setopt_long(opt::HTTPAUTH, val),
+            HttpContentDecoding(enable) => self.easy_setopt_bool(opt::HTTP_CONTENT_DECODING, enable),
+            HttpGet(enable) => self.easy_setopt_bool(opt::HTTPGET, enable),
+            HttpHeader(vals) => self.easy_setopt_strs(opt::HTTPHEADER, vals),
+            HttpProxyTunnel(enable) => self.easy_setopt_bool(opt::HTTPPROXYTUNNEL, enable),
+            HttpTransferDecoding(enable) => self.easy_setopt_bool(opt::HTTP_TRANSFER_DECODING, enable),
+            HttpVersion(val) => self.easy_setopt_long(opt::HTTP_VERSION, val),
+            IgnoreContentLength(enable) => self.easy_setopt_bool(opt::IGNORE_CONTENT_LENGTH, enable),
+            InFileSize(val) => self.easy_setopt_long(opt::INFILESIZE, val),
+            InFileSizeLarge(val) => self.easy_setopt_off_t(opt::INFILESIZE_LARGE, val),
+            Interface(val) => self.easy_setopt_str(opt::INTERFACE, val),
+            IpResolve(val) => self.easy_setopt_long(opt::IPRESOLVE, val),
+            IssuerCert(val) => self.easy_setopt_str(opt::ISSUERCERT, val),
+            IssuerCertBlob(data) => self.easy_setopt_blob(opt::ISSUERCERT_BLOB, data),
+            KeyPasswd(val) => self.easy_setopt_str(opt::KEYPASSWD, val),
+            KrbLevel(val) => self.easy_setopt_str(opt::KRBLEVEL, val),
+            LocalPort(val) => self.easy_setopt_long(opt::LOCALPORT, val),
+            LocalPortRange(val) => self.easy_setopt_long(opt::LOCALPORTRANGE, val),
+            LowSpeedLimit(val) => self.easy_setopt_long(opt::LOW_SPEED_LIMIT, val),
+            LowSpeedTime(val) => self.easy_setopt_long(opt::LOW_SPEED_TIME, val),
+            MailAuth(val) => self.easy_setopt_str(opt::MAIL_AUTH, val),
+            MailFrom(val) => self.easy_setopt_str(opt::MAIL_FROM, val),
+            MailRcpt(vals) => self.easy_setopt_strs(opt::MAIL_RCPT, vals),
+            MaxConnects(val) => self.easy_setopt_long(opt::MAXCONNECTS, val),
+            MaxFileSize(val) => self.easy_setopt_long(opt::MAXFILESIZE, val),
+            MaxFileSizeLarge(val) => self.easy_setopt_off_t(opt::MAXFILESIZE_LARGE, val),
+            MaxRecvSpeedLarge(val) => self.easy_setopt_off_t(opt::MAX_RECV_SPEED_LARGE, val),
+            MaxRedirects(val) => self.easy_setopt_long(opt::MAXREDIRS, val),
+            MaxSendSpeedLarge(val) => self.easy_setopt_off_t(opt::MAX_SEND_SPEED_LARGE, val),
+            Netrc(val) => self.easy_setopt_long(opt::NETRC, val),
+            NetrcFile(val) => self.easy_setopt_str(opt::NETRC_FILE, val),
+            NewDirectoryPerms(val) => self.easy_setopt_long(opt::NEW_DIRECTORY_PERMS, val),
+            NewFilePerms(val) => self.easy_setopt_long(opt::NEW_FILE_PERMS, val),
+            NoBody(enable) => self.easy_setopt_bool(opt::NOBODY, enable),
+            NoProgress(enable) => self.easy_setopt_bool(opt::NOPROGRESS, enable),
+            NoProxy(val) => self.easy_setopt_str(opt::NOPROXY, val),
+            NoSignal(enable) => self.easy_setopt_bool(opt::NOSIGNAL, enable),
+            Password(val) => self.easy_setopt_str(opt::PASSWORD, val),
+            Port(val) => self.easy_setopt_long(opt::PORT, val),
+            Post(enable) => self.easy_setopt_bool(opt::POST, enable),
+            PostFields(data) => {
+                // libcurl copies exactly POSTFIELDSIZE bytes, so the body may hold NULs
+                try!(self.easy_setopt_off_t(opt::POSTFIELDSIZE_LARGE, data.len() as i64));
+                unsafe { self.easy_setopt_ptr(opt::COPYPOSTFIELDS, data.as_ptr() as *c_void) }
+            }
+            PinnedPublicKey(val) => self.easy_setopt_str(opt::PINNEDPUBLICKEY, val),
+            PostFieldSize(val) => self.easy_setopt_long(opt::POSTFIELDSIZE, val),
+            PostFieldSizeLarge(val) => self.easy_setopt_off_t(opt::POSTFIELDSIZE_LARGE, val),
+            PostQuote(vals) => self.easy_setopt_strs(opt::POSTQUOTE, vals),
+            PostRedir(val) => self.easy_setopt_long(opt::POSTREDIR, val),
+            PreQuote(vals) => self.easy_setopt_strs(opt::PREQUOTE, vals),
+            Protocols(val) => self.easy_setopt_long(opt::PROTOCOLS, val),
             Proxy(proxy, user, pass) => {
-                self.easy_setopt_str(opt::PROXY, proxy);
+                try!(self.easy_setopt_str(opt::PROXY, proxy));
                 match user {
-                    Some(u) => self.easy_setopt_str(opt::PROXYUSERNAME, u),
-                    None => code::CURLE_OK
+                    Some(u) => try!(self.easy_setopt_str(opt::PROXYUSERNAME, u)),
+                    None => { ; }
                 };
                 match pass {
                     Some(p) => self.easy_setopt_str(opt::PROXYPASSWORD, p),
-                    None => code::CURLE_OK
+                    None => Ok(())
                 }
             },
-            Referer(referer) => self.easy_setopt_str(opt::REFERER, referer),
+            ProxyAuth(val) => self.easy_setopt_long(opt::PROXYAUTH, val),
+            ProxyCaInfoBlob(data) => self.easy_setopt_blob(opt::PROXY_CAINFO_BLOB, data),
+            ProxyIssuerCertBlob(data) => self.easy_setopt_blob(opt::PROXY_ISSUERCERT_BLOB, data),
+            ProxyPassword(val) => self.easy_setopt_str(opt::PROXYPASSWORD, val),
+            ProxyPort(val) => self.easy_setopt_long(opt::PROXYPORT, val),
+            ProxySslCertBlob(data) => self.easy_setopt_blob(opt::PROXY_SSLCERT_BLOB, data),
+            ProxySslKeyBlob(data) => self.easy_setopt_blob(opt::PROXY_SSLKEY_BLOB, data),
+            ProxyTransferMode(enable) => self.easy_setopt_bool(opt::PROXY_TRANSFER_MODE, enable),
+            ProxyType(val) => self.easy_setopt_long(opt::PROXYTYPE, val),
+            ProxyUsername(val) => self.easy_setopt_str(opt::PROXYUSERNAME, val),
+            ProxyUserPwd(val) => self.easy_setopt_str(opt::PROXYUSERPWD, val),
+            Put(enable) => self.easy_setopt_bool(opt::PUT, enable),
+            Quote(vals) => self.easy_setopt_strs(opt::QUOTE, vals),
+            RandomFile(val) => self.easy_setopt_str(opt::RANDOM_FILE, val),
+            Range(val) => self.easy_setopt_str(opt::RANGE, val),
+            RedirProtocols(val) => self.easy_setopt_long(opt::REDIR_PROTOCOLS, val),
+            Referer(val) => self.easy_setopt_str(opt::REFERER, val),
+            Resolve(vals) => self.easy_setopt_strs(opt::RESOLVE, vals),
+            ResumeFrom(val) => self.easy_setopt_long(opt::RESUME_FROM, val),
+            ResumeFromLarge(val) => self.easy_setopt_off_t(opt::RESUME_FROM_LARGE, val),
+            RtspClientCseq(val) => self.easy_setopt_long(opt::RTSP_CLIENT_CSEQ, val),
+            RtspRequest(val) => self.easy_setopt_long(opt::RTSP_REQUEST, val),
+            RtspServerCseq(val) => self.easy_setopt_long(opt::RTSP_SERVER_CSEQ, val),
+            RtspSessionId(val) => self.easy_setopt_str(opt::RTSP_SESSION_ID, val),
+            RtspStreamUri(val) => self.easy_setopt_str(opt::RTSP_STREAM_URI, val),
+            RtspTransport(val) => self.easy_setopt_str(opt::RTSP_TRANSPORT, val),
+            SaslIr(enable) => self.easy_setopt_bool(opt::SASL_IR, enable),
             ShowHeaders(enable) => self.easy_setopt_bool(opt::HEADER, enable),
-            Timeout(secs) => self.easy_setopt_long(opt::TIMEOUT, secs),
+            Socks5GssapiNec(enable) => self.easy_setopt_bool(opt::SOCKS5_GSSAPI_NEC, enable),
+            Socks5GssapiService(val) => self.easy_setopt_str(opt::SOCKS5_GSSAPI_SERVICE, val),
+            SshAuthTypes(val) => self.easy_setopt_long(opt::SSH_AUTH_TYPES, val),
+            SshHostPublicKeyMd5(val) => self.easy_setopt_str(opt::SSH_HOST_PUBLIC_KEY_MD5, val),
+            SshKnownHosts(val) => self.easy_setopt_str(opt::SSH_KNOWNHOSTS, val),
+            SshPrivateKeyFile(val) => self.easy_setopt_str(opt::SSH_PRIVATE_KEYFILE, val),
+            SshPublicKeyFile(val) => self.easy_setopt_str(opt::SSH_PUBLIC_KEYFILE, val),
+            SslCert(val) => self.easy_setopt_str(opt::SSLCERT, val),
+            SslCertBlob(data) => self.easy_setopt_blob(opt::SSLCERT_BLOB, data),
+            SslCertType(val) => self.easy_setopt_str(opt::SSLCERTTYPE, val),
+            SslCipherList(val) => self.easy_setopt_str(opt::SSL_CIPHER_LIST, val),
+            SslEngine(val) => self.easy_setopt_str(opt::SSLENGINE, val),
+            SslEngineDefault(enable) => self.easy_setopt_bool(opt::SSLENGINE_DEFAULT, enable),
+            SslKey(val) => self.easy_setopt_str(opt::SSLKEY, val),
+            SslKeyBlob(data) => self.easy_setopt_blob(opt::SSLKEY_BLOB, data),
+            SslKeyType(val) => self.easy_setopt_str(opt::SSLKEYTYPE, val),
+            SslOptions(val) => self.easy_setopt_long(opt::SSL_OPTIONS, val),
+            SslSessionIdCache(enable) => self.easy_setopt_bool(opt::SSL_SESSIONID_CACHE, enable),
+            SslVerifyHost(enable) => self.easy_setopt_long(opt::SSL_VERIFYHOST, if enable { 2 } else { 0 }),
+            SslVerifyPeer(enable) => self.easy_setopt_bool(opt::SSL_VERIFYPEER, enable),
+            SslVersion(val) => self.easy_setopt_long(opt::SSLVERSION, val),
+            TcpKeepAlive(enable) => self.easy_setopt_bool(opt::TCP_KEEPALIVE, enable),
+            TcpKeepIdle(val) => self.easy_setopt_long(opt::TCP_KEEPIDLE, val),
+            TcpKeepIntvl(val) => self.easy_setopt_long(opt::TCP_KEEPINTVL, val),
+            TcpNoDelay(enable) => self.easy_setopt_bool(opt::TCP_NODELAY, enable),
+            TelnetOptions(vals) => self.easy_setopt_strs(opt::TELNETOPTIONS, vals),
+            TftpBlkSize(val) => self.easy_setopt_long(opt::TFTP_BLKSIZE, val),
+            TimeCondition(val) => self.easy_setopt_long(opt::TIMECONDITION, val),
+            Timeout(val) => self.easy_setopt_long(opt::TIMEOUT, val),
+            TimeoutMs(val) => self.easy_setopt_long(opt::TIMEOUT_MS, val),
+            TimeValue(val) => self.easy_setopt_long(opt::TIMEVALUE, val),
+            TlsAuthPassword(val) => self.easy_setopt_str(opt::TLSAUTH_PASSWORD, val),
+            TlsAuthType(val) => self.easy_setopt_str(opt::TLSAUTH_TYPE, val),
+            TlsAuthUsername(val) => self.easy_setopt_str(opt::TLSAUTH_USERNAME, val),
+            TransferEncoding(enable) => self.easy_setopt_bool(opt::TRANSFER_ENCODING, enable),
+            TransferText(enable) => self.easy_setopt_bool(opt::TRANSFERTEXT, enable),
+            UnrestrictedAuth(enable) => self.easy_setopt_bool(opt::UNRESTRICTED_AUTH, enable),
             UnsafeStringList(curlopt, slist) => self.easy_setopt_slist(curlopt, slist),
-            URL(url) => self.easy_setopt_str(opt::URL, url),
-            Username(user) => self.easy_setopt_str(opt::USERNAME, user),
+            Upload(enable) => self.easy_setopt_bool(opt::UPLOAD, enable),
+            URL(val) => self.easy_setopt_str(opt::URL, val),
+            UserAgent(val) => self.easy_setopt_str(opt::USERAGENT, val),
+            Username(val) => self.easy_setopt_str(opt::USERNAME, val),
+            UserPwd(val) => self.easy_setopt_str(opt::USERPWD, val),
+            UseSsl(val) => self.easy_setopt_long(opt::USE_SSL, val),
             VerboseMode(enable) => self.easy_setopt_bool(opt::VERBOSE, enable),
+            WildcardMatch(enable) => self.easy_setopt_bool(opt::WILDCARDMATCH, enable),
         }
     }
 
@@ -160,17 +577,34 @@ impl Curl {
     /// curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &body);
     /// ~~~    
     pub fn easy_setopt_callback<D, U, T: CurlCallback<D, U>>(&self, dataOpt: opt::CURLoption, 
-        callbackOpt: opt::CURLoption, callback: &T) -> code::CURLcode {
+        callbackOpt: opt::CURLoption, callback: &T) -> CurlResult<()> {
+        try!(self.check_type(dataOpt, [opt::ObjectPointType]));
+        try!(self.check_type(callbackOpt, [opt::FunctionPointType]));
+
         let data_val = callback.curl_get_userdata();
         let fn_val = callback.curl_get_callback();
         unsafe {           
-            fail_on_curl_error(curl_easy_setopt(self.curl, dataOpt, mem::transmute(data_val)));
-            fail_on_curl_error(curl_easy_setopt(self.curl, callbackOpt, mem::transmute(fn_val)));
+            try!(self.check(curl_easy_setopt(self.curl, dataOpt, mem::transmute(data_val))));
+            self.check(curl_easy_setopt(self.curl, callbackOpt, mem::transmute(fn_val)))
         }
-        code::CURLE_OK
     }
     
+    /// Post `mime` as a multipart/form-data body. The handle keeps the form
+    /// until the option is set again, or the handle is reset or dropped.
+    /// Clones of the handle don't get the form.
+    /// # Arguments
+    /// * `mime` -  the form, built for this handle
+    pub fn easy_setopt_mime(&self, mime: CurlMime) -> CurlResult<()> {
+        unsafe {
+            try!(self.easy_setopt_ptr(opt::MIMEPOST, mime.handle()));
+        }
+        self.keep(opt::MIMEPOST, OwnedMime(mime));
+        Ok(())
+    }
+
     /// Wrapper over curl_easy_perform (performs the request).
+    /// On failure the error carries curl's detailed message, the OS errno
+    /// and the URL in use.
     /// # Example
     /// ~~~ {.rust}
     /// let curl = Curl::new();
@@ -180,9 +614,10 @@ impl Curl {
     /// curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &my_callback);
     /// curl.easy_perform();
     /// ~~~
-    pub fn easy_perform(&self) -> code::CURLcode {
+    pub fn easy_perform(&self) -> CurlResult<()> {
+        self.clear_errbuf();
         unsafe {
-            curl_easy_perform(self.curl)
+            self.check_transfer(curl_easy_perform(self.curl))
         }
     }
 
@@ -198,44 +633,270 @@ impl Curl {
         unsafe {
             curl_easy_reset(self.curl);
         }
+        self.owned.borrow_mut().clear();
+        self.set_errbuf();
+
+        match self.share {
+            Some(ref share) => unsafe {
+                curl_easy_setopt(self.curl, opt::SHARE, share.handle());
+            },
+            None => { ; }
+        }
+    }
+
+    /// Use the given share handle, so this handle shares its DNS cache,
+    /// cookies, TLS sessions and/or connections with every other handle
+    /// using it. The handle keeps the share alive for as long as it uses it.
+    /// # Arguments
+    /// * `share` - the share handle
+    /// # Example
+    /// ~~~ {.rust}
+    /// let share = Arc::new(CurlShare::new([ShareDns]).unwrap());
+    /// let mut curl = Curl::new();
+    /// curl.easy_share(share.clone());
+    /// ~~~
+    pub fn easy_share(&mut self, share: Arc<CurlShare>) -> CurlResult<()> {
+        unsafe {
+            try!(self.easy_setopt_ptr(opt::SHARE, share.handle()));
+        }
+        self.share = Some(share);
+        Ok(())
+    }
+
+    /// Wrapper over curl_easy_getinfo, which retrieves information about
+    /// the last transfer performed on this handle.
+    /// # Arguments
+    /// * `info` - the piece of information to retrieve
+    /// CURLINFO_CERTINFO is only filled in when the CertInfo option was set
+    /// and the transfer made a new TLS connection.
+    /// # Example
+    /// ~~~ {.rust}
+    /// use curl::curl_ll::CURLINFO_RESPONSE_CODE;
+    ///
+    /// let curl = Curl::new();
+    /// curl.easy_setopt(URL("www.google.com"));
+    /// // omitted setting a WRITEFUNCTION
+    /// curl.easy_perform();
+    /// let status = curl.easy_getinfo(CURLINFO_RESPONSE_CODE);
+    /// ~~~
+    pub fn easy_getinfo(&self, info: CURLINFO) -> CurlResult<CurlInfo> {
+        use std::str::raw::from_c_str;
+
+        let kind = info as int & CURLINFO_TYPEMASK;
+
+        unsafe {
+            if kind == CURLINFO_STRING {
+                let mut raw: *c_char = ptr::null();
+                let rc = curl_easy_getinfo(self.curl, info, &mut raw as *mut *c_char as *c_void);
+                if rc != code::CURLE_OK { return Err(CurlError::new(rc)); }
+
+                match raw.is_null() {
+                    true => Ok(InfoString(String::new())),
+                    false => Ok(InfoString(from_c_str(raw)))
+                }
+            } else if kind == CURLINFO_LONG {
+                let mut val: c_long = 0;
+                let rc = curl_easy_getinfo(self.curl, info, &mut val as *mut c_long as *c_void);
+                if rc != code::CURLE_OK { return Err(CurlError::new(rc)); }
+
+                Ok(InfoLong(val as i64))
+            } else if kind == CURLINFO_DOUBLE {
+                let mut val: c_double = 0.0;
+                let rc = curl_easy_getinfo(self.curl, info, &mut val as *mut c_double as *c_void);
+                if rc != code::CURLE_OK { return Err(CurlError::new(rc)); }
+
+                Ok(InfoDouble(val as f64))
+            } else if info as int == CURLINFO_CERTINFO as int {
+                // owned by the handle, unlike the other lists
+                let mut certs: *curl_certinfo = ptr::null();
+                let rc = curl_easy_getinfo(self.curl, info, &mut certs as *mut *curl_certinfo as *c_void);
+                if rc != code::CURLE_OK { return Err(CurlError::new(rc)); }
+
+                let mut ret = vec![];
+                if !certs.is_null() {
+                    for i in range(0, (*certs).num_of_certs as int) {
+                        ret.push(slist_to_vec(*(*certs).certinfo.offset(i)));
+                    }
+                }
+                Ok(InfoCertChain(ret))
+            } else if kind == CURLINFO_SLIST {
+                let mut list: *curl_slist = ptr::null();
+                let rc = curl_easy_getinfo(self.curl, info, &mut list as *mut *curl_slist as *c_void);
+                if rc != code::CURLE_OK { return Err(CurlError::new(rc)); }
+
+                let ret = slist_to_vec(list);
+                curl_slist_free_all(list);
+                Ok(InfoList(ret))
+            } else {
+                Err(CurlError::new(code::CURLE_BAD_FUNCTION_ARGUMENT))
+            }
+        }
+    }
+
+    /// Points CURLOPT_ERRORBUFFER at this handle's own buffer
+    fn set_errbuf(&self) {
+        unsafe {
+            curl_easy_setopt(self.curl, opt::ERRORBUFFER, self.errbuf.as_ptr() as *c_void);
+        }
+    }
+
+    /// Clears any message left in the error buffer by a previous transfer
+    fn clear_errbuf(&self) {
+        unsafe {
+            *(self.errbuf.as_ptr() as *mut c_char) = 0;
+        }
+    }
+
+    /// Turns a code returned by a call other than curl_easy_perform into a
+    /// result. The handle's error buffer, errno and URL describe the last
+    /// transfer, not this call, so only the generic description is used.
+    fn check(&self, c: code::CURLcode) -> CurlResult<()> {
+        match c {
+            code::CURLE_OK => Ok(()),
+            c => Err(CurlError::new(c))
+        }
+    }
+
+    /// Turns the code returned by curl_easy_perform into a result, building
+    /// the error from the details curl left on the handle
+    fn check_transfer(&self, c: code::CURLcode) -> CurlResult<()> {
+        use std::str::raw::from_c_str;
+
+        if c == code::CURLE_OK {
+            return Ok(());
+        }
+
+        let detail = unsafe { from_c_str(self.errbuf.as_ptr()) };
+        let message = match detail.as_slice().trim() {
+            "" => easy_strerror(c),
+            d => d.to_string()
+        };
+
+        let os_errno = match self.easy_getinfo(CURLINFO_OS_ERRNO) {
+            Ok(InfoLong(errno)) if errno != 0 => Some(errno),
+            _ => None
+        };
+
+        let url = match self.easy_getinfo(CURLINFO_EFFECTIVE_URL) {
+            Ok(InfoString(ref url)) if !url.is_empty() => Some(url.clone()),
+            _ => None
+        };
+
+        Err(CurlError { code: c, message: message, os_errno: os_errno, url: url })
+    }
+
+    /// Fails with CURLE_BAD_FUNCTION_ARGUMENT unless `opt` takes one of `types`,
+    /// so a setter can never hand libcurl a value of the wrong C type
+    fn check_type(&self, opt: opt::CURLoption, types: &[opt::OptionType]) -> CurlResult<()> {
+        let actual = opt.option_type();
+        if types.contains(&actual) {
+            return Ok(());
+        }
+
+        let mut err = CurlError::new(code::CURLE_BAD_FUNCTION_ARGUMENT);
+        err.message = format!("option {} takes a {} value, not a {}", opt as int, actual, types[0]);
+        Err(err)
     }
 
-    // TODO the below need to be checked against their option types to ensure no failure occurs
+    fn easy_setopt_str(&self, opt: opt::CURLoption, string: &str) -> CurlResult<()> {
+        self.easy_setopt_c_str(opt, try!(checked_c_str(string)))
+    }
 
-    fn easy_setopt_str(&self, opt: opt::CURLoption, string: &str) -> code::CURLcode {
-        string.with_c_str(|c_str| {
+    fn easy_setopt_c_str(&self, opt: opt::CURLoption, c_str: CString) -> CurlResult<()> {
+        try!(self.check_type(opt, [opt::ObjectPointType]));
+        try!(c_str.with_ref(|p| {
             unsafe {
-                fail_on_curl_error(curl_easy_setopt(self.curl, opt, c_str as *c_void))
+                self.check(curl_easy_setopt(self.curl, opt, p as *c_void))
             }
-        })
+        }));
+
+        self.keep(opt, OwnedString(c_str));
+        Ok(())
     }
 
-    fn easy_setopt_slist(&self, opt: opt::CURLoption, val: *curl_slist) -> code::CURLcode {
+    fn easy_setopt_slist(&self, opt: opt::CURLoption, val: *curl_slist) -> CurlResult<()> {
+        try!(self.check_type(opt, [opt::ObjectPointType]));
         unsafe {
             let opt_val = mem::transmute(val);
-            fail_on_curl_error(curl_easy_setopt(self.curl, opt, opt_val))
+            self.check(curl_easy_setopt(self.curl, opt, opt_val))
+        }
+    }
+
+    /// Builds an slist out of `vals` and sets it
+    fn easy_setopt_strs(&self, opt: opt::CURLoption, vals: &[&str]) -> CurlResult<()> {
+        let mut c_strs = Vec::with_capacity(vals.len());
+        for v in vals.iter() {
+            c_strs.push(try!(checked_c_str(*v)));
+        }
+
+        let mut list = 0 as *curl_slist;
+        for c_str in c_strs.iter() {
+            c_str.with_ref(|s| {
+                unsafe {
+                    list = curl_slist_append(list, s);
+                }
+            });
+        }
+
+        self.easy_setopt_owned_slist(opt, list)
+    }
+
+    /// Sets `list` and keeps it, or frees it if it was refused
+    fn easy_setopt_owned_slist(&self, opt: opt::CURLoption, list: *curl_slist) -> CurlResult<()> {
+        match self.easy_setopt_slist(opt, list) {
+            Ok(()) => { ; }
+            Err(err) => {
+                unsafe { curl_slist_free_all(list); }
+                return Err(err);
+            }
+        }
+
+        self.keep(opt, OwnedList(list));
+        Ok(())
+    }
+
+    /// Holds on to a value given to libcurl for `opt`, dropping the one it replaces
+    fn keep(&self, opt: opt::CURLoption, value: OwnedValue) {
+        let mut owned = self.owned.borrow_mut();
+        match owned.iter().position(|&(o, _)| o as int == opt as int) {
+            Some(i) => { owned.remove(i); }
+            None => { ; }
+        }
+        owned.push((opt, value));
+    }
+
+    fn easy_setopt_blob(&self, opt: opt::CURLoption, data: &[u8]) -> CurlResult<()> {
+        try!(self.check_type(opt, [opt::BlobType]));
+
+        // the data is copied, the blob itself only has to outlive the call
+        let blob = curl_blob { data: data.as_ptr() as *c_void, len: data.len() as size_t, flags: CURL_BLOB_COPY };
+        unsafe {
+            self.easy_setopt_ptr(opt, &blob as *curl_blob as *c_void)
         }
     }
 
     #[inline]
-    fn easy_setopt_bool(&self, opt: opt::CURLoption, val: bool) -> code::CURLcode {
+    fn easy_setopt_bool(&self, opt: opt::CURLoption, val: bool) -> CurlResult<()> {
         self.easy_setopt_long(opt, val as int)
     }
 
-    fn easy_setopt_long(&self, opt: opt::CURLoption, val: int) -> code::CURLcode {
+    fn easy_setopt_long(&self, opt: opt::CURLoption, val: int) -> CurlResult<()> {
+        try!(self.check_type(opt, [opt::LongType]));
         unsafe {
-            fail_on_curl_error(curl_easy_setopt(self.curl, opt, val as *c_void))
+            self.check(curl_easy_setopt(self.curl, opt, val as *c_void))
         }
     }
-}
 
+    fn easy_setopt_off_t(&self, opt: opt::CURLoption, val: i64) -> CurlResult<()> {
+        try!(self.check_type(opt, [opt::OffTType]));
+        unsafe {
+            self.check(curl_easy_setopt(self.curl, opt, val as *c_void))
+        }
+    }
 
-/// Function that fails on any non-OK status code from CURL
-#[inline]
-fn fail_on_curl_error(c: code::CURLcode) -> code::CURLcode {
-    match c {
-        code::CURLE_OK => c,
-        _ => fail!(easy_strerror(c))
+    unsafe fn easy_setopt_ptr(&self, opt: opt::CURLoption, val: *c_void) -> CurlResult<()> {
+        try!(self.check_type(opt, [opt::ObjectPointType, opt::FunctionPointType, opt::BlobType]));
+        self.check(curl_easy_setopt(self.curl, opt, val))
     }
 }
 
@@ -249,8 +910,10 @@ fn fail_on_curl_error(c: code::CURLcode) -> code::CURLcode {
 /// curl.easy_setopt_str(opt::URL, "www.google.com");
 /// // omitted a few easy_setopt calls, but you need to either set a WRITEFUNCTION
 /// // or a FILE* as the WRITEDATA to avoid a segfault
-/// let err = curl.easy_perform();
-/// let err_str = easy_strerror(err);
+/// match curl.easy_perform() {
+///     Ok(()) => { ; }
+///     Err(err) => { println!("{}", easy_strerror(err.code)); }
+/// };
 /// ~~~
 pub fn easy_strerror(c: code::CURLcode) -> String {
     use std::str::raw::from_c_str;
@@ -262,6 +925,49 @@ pub fn easy_strerror(c: code::CURLcode) -> String {
     }
 }
 
+/// `s` as a C string, or CURLE_BAD_FUNCTION_ARGUMENT if it has a NUL byte,
+/// which would cut it short
+fn checked_c_str(s: &str) -> CurlResult<CString> {
+    if s.contains_char('\0') {
+        let mut err = CurlError::new(code::CURLE_BAD_FUNCTION_ARGUMENT);
+        err.message = format!("\"{}\" contains a NUL byte", s.escape_default());
+        return Err(err);
+    }
+    Ok(s.to_c_str())
+}
+
+/// Copies a curl_slist, strings and all, without going through Rust strings
+fn copy_slist(list: *curl_slist) -> *curl_slist {
+    let mut copy = 0 as *curl_slist;
+    let mut cur = list;
+
+    unsafe {
+        while !cur.is_null() {
+            copy = curl_slist_append(copy, (*cur).data);
+            cur = (*cur).next;
+        }
+    }
+
+    copy
+}
+
+/// Copies the strings of a curl_slist into a vector, leaving the list untouched
+fn slist_to_vec(list: *curl_slist) -> Vec<String> {
+    use std::str::raw::from_c_str;
+
+    let mut ret = vec![];
+    let mut cur = list;
+
+    unsafe {
+        while !cur.is_null() {
+            ret.push(from_c_str((*cur).data));
+            cur = (*cur).next;
+        }
+    }
+
+    ret
+}
+
 /// Convenience function to fetch the body of HTTP response at the
 /// given URL. You are responsible for ensuring it's properly escaped/
 /// # Arguments
@@ -274,30 +980,46 @@ pub fn easy_strerror(c: code::CURLcode) -> String {
 ///
 /// match data_res {
 ///     Ok(data) => { println(from_bytes(data)); }
-///     Err(msg) => { fail!("Error" + msg); }
+///     Err(err) => { fail!("Error {}", err); }
 /// };
 /// ~~~
-pub fn get(url: &str) -> Result<Vec<u8>,String> {
+pub fn get(url: &str) -> CurlResult<Vec<u8>> {
     let curl = Curl::new();
 
-    let buf = SimpleCurlByteBuffer::new();
+    let mut buf = vec![];
 
-    curl.easy_setopt_str(opt::URL, url);
-    curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &buf);
+    try!(curl.easy_setopt_str(opt::URL, url));
+    try!(curl.easy_perform_with(&mut CurlHandlers::new().write(|data| { buf.push_all(data); data.len() })));
 
-    let err = curl.easy_perform();
-
-    match err {
-        code::CURLE_OK => { Ok(buf.data) }
-        _ => { Err(easy_strerror(err)) }
-    }
+    Ok(buf)
 }
 
 impl Clone for Curl {
     fn clone(&self) -> Curl {
-        unsafe {
-            Curl {curl: curl_easy_duphandle(self.curl)}
+        let curl = unsafe {
+            Curl {curl: curl_easy_duphandle(self.curl), errbuf: Vec::from_elem(CURL_ERROR_SIZE, 0 as c_char),
+                  share: self.share.clone(), owned: RefCell::new(vec![])}
+        };
+        // the duplicate would otherwise write its errors into our buffer
+        curl.set_errbuf();
+
+        // libcurl copies our strings into the duplicate only on versions that
+        // copy strings at all, and never our lists, so give it its own copies,
+        // byte for byte
+        for &(opt, ref value) in self.owned.borrow().iter() {
+            let res = match *value {
+                OwnedString(ref val) => curl.easy_setopt_c_str(opt, val.as_bytes_no_nul().to_c_str()),
+                OwnedList(list) => curl.easy_setopt_owned_slist(opt, copy_slist(list)),
+                // a form may stream from a reader, which can't be shared
+                OwnedMime(_) => unsafe { curl.easy_setopt_ptr(opt, ptr::null()) }
+            };
+            if res.is_err() {
+                // the duplicate must not point at our copy, which dies with us;
+                // clearing a pointer can't fail for an option accepted before
+                let _ = unsafe { curl.easy_setopt_ptr(opt, ptr::null()) };
+            }
         }
+        curl
     }
 }
 
@@ -307,6 +1029,7 @@ impl Drop for Curl {
         unsafe {
             curl_easy_cleanup(self.curl);
         }
+        self.owned.borrow_mut().clear();
     }
 }
 
@@ -333,6 +1056,9 @@ mod test {
 
         assert!(escaped_query == ~"lol%20and%20stuff");
         assert!(unescaped_query == query);
+
+        assert_eq!(c1.easy_escape("a\0b"), "a%00b".to_string());
+        assert_eq!(c1.easy_unescape("a%00b"), "a\0b".to_string());
     }
 
     #[test]
@@ -345,10 +1071,10 @@ mod test {
         curl.easy_setopt_long(opt::HEADER, 1);
         curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &buf);
         
-        let err = curl.easy_perform();
+        let res = curl.easy_perform();
 
         assert!(!buf.data.is_empty());
-        assert!(err == code::CURLE_OK);
+        assert!(res.is_ok());
     }
 
     #[test]
@@ -357,17 +1083,148 @@ mod test {
 
         match data_res {
             Ok(_) => { ; }
-            Err(msg) => { fail!("Error" + msg); }
+            Err(err) => { fail!("Error {}", err); }
         };
     }
 
-    #[test] #[should_fail]
-    fn test_invalid_params_should_fail() {
+    #[test]
+    fn test_easy_getinfo() {
+        use curl::curl_ll::{CURLINFO_RESPONSE_CODE, CURLINFO_EFFECTIVE_URL, CURLINFO_TOTAL_TIME,
+                            CURLINFO_CERTINFO};
+
+        let curl = Curl::new();
+        let buf = SimpleCurlByteBuffer::new();
+
+        curl.easy_setopt(URL("http://www.google.com"));
+        curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &buf);
+
+        assert!(curl.easy_perform().is_ok());
+
+        let status = curl.easy_getinfo(CURLINFO_RESPONSE_CODE).unwrap();
+        assert!(status.as_long().unwrap() > 0);
+
+        let url = curl.easy_getinfo(CURLINFO_EFFECTIVE_URL).unwrap();
+        assert!(url.as_str().unwrap().starts_with("http://www.google."));
+
+        let total = curl.easy_getinfo(CURLINFO_TOTAL_TIME).unwrap();
+        assert!(total.as_double().unwrap() > 0.0);
+
+        // plain http, so there is no chain
+        let certs = curl.easy_getinfo(CURLINFO_CERTINFO).unwrap();
+        assert!(certs.as_cert_chain().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_slist_options_outlive_clone() {
+        use std::str::from_utf8;
+
+        let curl = Curl::new();
+        curl.easy_setopt(URL("http://httpbin.org/headers")).unwrap();
+        curl.easy_setopt(HttpHeader(["X-Replaced: 1"])).unwrap();
+        curl.easy_setopt(HttpHeader(["X-First: a", "X-Second: b"])).unwrap();
+
+        let copy = curl.clone();
+        drop(curl);
+
+        let buf = SimpleCurlByteBuffer::new();
+        copy.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &buf).unwrap();
+        assert!(copy.easy_perform().is_ok());
+
+        let echoed = from_utf8(buf.data.as_slice()).unwrap();
+        assert!(echoed.contains("X-First"));
+        assert!(echoed.contains("X-Second"));
+        assert!(!echoed.contains("X-Replaced"));
+    }
+
+    #[test]
+    fn test_owned_values_are_replaced_and_reset() {
+        let curl = Curl::new();
+        {
+            let url = format!("http://{}/", "example.com");
+            curl.easy_setopt(URL(url.as_slice())).unwrap();
+        }
+        curl.easy_setopt(URL("http://example.org/")).unwrap();
+        curl.easy_setopt(HttpHeader(["Accept: */*"])).unwrap();
+        assert_eq!(curl.owned.borrow().len(), 2);
+
+        curl.easy_reset();
+        assert!(curl.owned.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_option_types() {
+        assert_eq!(opt::VERBOSE.option_type(), opt::LongType);
+        assert_eq!(opt::URL.option_type(), opt::ObjectPointType);
+        assert_eq!(opt::WRITEFUNCTION.option_type(), opt::FunctionPointType);
+        assert_eq!(opt::INFILESIZE_LARGE.option_type(), opt::OffTType);
+        assert_eq!(opt::SSLCERT_BLOB.option_type(), opt::BlobType);
+    }
+
+    #[test]
+    fn test_wrong_option_type_is_rejected() {
+        use curl::code;
+
+        let curl = Curl::new();
+
+        let err = curl.easy_setopt_long(opt::URL, 1).unwrap_err();
+        assert!(err.code == code::CURLE_BAD_FUNCTION_ARGUMENT);
+
+        assert!(curl.easy_setopt_str(opt::VERBOSE, "1").is_err());
+        assert!(curl.easy_setopt_off_t(opt::TIMEOUT, 1).is_err());
+        assert!(curl.easy_setopt_blob(opt::CAINFO, [0u8]).is_err());
+
+        let buf = SimpleCurlByteBuffer::new();
+        assert!(curl.easy_setopt_callback(opt::WRITEFUNCTION, opt::WRITEDATA, &buf).is_err());
+    }
+
+    #[test]
+    fn test_nul_in_string_is_rejected() {
+        use curl::code;
+
+        let curl = Curl::new();
+
+        let err = curl.easy_setopt(URL("http://example.com/\0")).unwrap_err();
+        assert!(err.code == code::CURLE_BAD_FUNCTION_ARGUMENT);
+        assert!(curl.easy_setopt(HttpHeader(["Accept: */*", "X-Evil: a\0b"])).is_err());
+        assert!(curl.easy_setopt(HttpHeader(["Accept: */*"])).is_ok());
+    }
+
+    #[test]
+    fn test_perform_error_details() {
+        let curl = Curl::new();
+        let buf = SimpleCurlByteBuffer::new();
+
+        curl.easy_setopt(URL("http://nonexistent.invalid/"));
+        curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &buf);
+
+        match curl.easy_perform() {
+            Ok(()) => { fail!("resolving an .invalid host should fail"); }
+            Err(err) => {
+                assert!(err.is_dns());
+                assert!(!err.message.is_empty());
+                assert_eq!(err.url, Some("http://nonexistent.invalid/".to_string()));
+            }
+        }
+
+        // a later failing setopt doesn't report the transfer's details
+        let err = curl.easy_setopt(SslVersion(100)).unwrap_err();
+        assert_eq!(err.message, easy_strerror(err.code));
+        assert_eq!(err.url, None);
+    }
+
+    #[test]
+    fn test_invalid_params_return_error() {
         // create a NULL pointer manually and try to pass it into a setopt function
-        use curl::curl_ll::CURL;
-        let curl = Curl { curl: 0 as *CURL };
+        use curl::curl_ll::{CURL, CURL_ERROR_SIZE};
+        use libc::c_char;
+        use std::cell::RefCell;
+        let curl = Curl { curl: 0 as *CURL, errbuf: Vec::from_elem(CURL_ERROR_SIZE, 0 as c_char), share: None,
+                          owned: RefCell::new(vec![]) };
 
-        // should fail in the curl library and cause task failure
-        curl.easy_setopt_long(opt::HEADER, 1);
+        // should be rejected by the curl library and reported as an error
+        match curl.easy_setopt_long(opt::HEADER, 1) {
+            Ok(()) => { fail!("setting an option on a NULL handle should fail"); }
+            Err(err) => { assert!(err.code == code::CURLE_BAD_FUNCTION_ARGUMENT); }
+        }
     }
 }
@@ -1,14 +1,61 @@
-use libc::{c_long, c_int, c_char, c_void};
+use libc::{c_long, c_int, c_uint, c_char, c_void, size_t};
 
-use curl::code::CURLcode;
+use curl::code::{CURLcode, CURLMcode, CURLSHcode};
 use curl::opt::CURLoption;
 
-static CURLINFO_STRING: int = 0x100000;
-static CURLINFO_LONG: int = 0x200000;
-static CURLINFO_DOUBLE: int = 0x300000;
-static CURLINFO_SLIST: int = 0x400000;
-static CURLINFO_MASK: int = 0x0fffff;
-static CURLINFO_TYPEMASK: int = 0xf00000;
+pub static CURLINFO_STRING: int = 0x100000;
+pub static CURLINFO_LONG: int = 0x200000;
+pub static CURLINFO_DOUBLE: int = 0x300000;
+pub static CURLINFO_SLIST: int = 0x400000;
+pub static CURLINFO_MASK: int = 0x0fffff;
+pub static CURLINFO_TYPEMASK: int = 0xf00000;
+
+/// The minimum size of the buffer given to CURLOPT_ERRORBUFFER
+pub static CURL_ERROR_SIZE: uint = 256;
+
+/// Returned from a read callback to abort the transfer
+pub static CURL_READFUNC_ABORT: size_t = 0x10000000;
+
+/// Protocol bits for CURLOPT_PROTOCOLS and CURLOPT_REDIR_PROTOCOLS
+pub static CURLPROTO_HTTP: int = 1 << 0;
+pub static CURLPROTO_HTTPS: int = 1 << 1;
+pub static CURLPROTO_FTP: int = 1 << 2;
+pub static CURLPROTO_FTPS: int = 1 << 3;
+pub static CURLPROTO_SCP: int = 1 << 4;
+pub static CURLPROTO_SFTP: int = 1 << 5;
+pub static CURLPROTO_FILE: int = 1 << 10;
+
+/// Bits for CURLOPT_POSTREDIR: keep POSTing after these redirects
+pub static CURL_REDIR_POST_301: int = 1;
+pub static CURL_REDIR_POST_302: int = 2;
+pub static CURL_REDIR_POST_303: int = 4;
+pub static CURL_REDIR_POST_ALL: int = 7;
+
+/// Values for CURLOPT_PROXYTYPE
+pub static CURLPROXY_HTTP: int = 0;
+pub static CURLPROXY_HTTP_1_0: int = 1;
+pub static CURLPROXY_HTTPS: int = 2;
+pub static CURLPROXY_SOCKS4: int = 4;
+pub static CURLPROXY_SOCKS5: int = 5;
+pub static CURLPROXY_SOCKS4A: int = 6;
+pub static CURLPROXY_SOCKS5_HOSTNAME: int = 7;
+
+/// Bits for CURLOPT_HTTPAUTH and CURLOPT_PROXYAUTH
+pub static CURLAUTH_NONE: int = 0;
+pub static CURLAUTH_BASIC: int = 1 << 0;
+pub static CURLAUTH_DIGEST: int = 1 << 1;
+pub static CURLAUTH_NEGOTIATE: int = 1 << 2;
+pub static CURLAUTH_NTLM: int = 1 << 3;
+pub static CURLAUTH_DIGEST_IE: int = 1 << 4;
+pub static CURLAUTH_ANY: int = !CURLAUTH_DIGEST_IE;
+pub static CURLAUTH_ANYSAFE: int = !(CURLAUTH_BASIC | CURLAUTH_DIGEST_IE);
+
+/// Values for CURLOPT_SSLVERSION: the lowest TLS version to accept
+pub static CURL_SSLVERSION_DEFAULT: int = 0;
+pub static CURL_SSLVERSION_TLSv1_0: int = 4;
+pub static CURL_SSLVERSION_TLSv1_1: int = 5;
+pub static CURL_SSLVERSION_TLSv1_2: int = 6;
+pub static CURL_SSLVERSION_TLSv1_3: int = 7;
 
 pub enum CURLINFO {
   CURLINFO_NONE, /* first, never use this */
@@ -65,11 +112,73 @@ pub enum CURLINFO {
 ///     that is obviously an unsafe structure which should
 ///     be used properly and cautiously
 pub struct curl_slist {
-    data: *c_char,
-    next: *curl_slist
+    pub data: *c_char,
+    pub next: *curl_slist
 }
 
+/// The peer certificate chain returned for CURLINFO_CERTINFO: one
+/// slist of "Name:value" lines per certificate, the server's first
+pub struct curl_certinfo {
+    pub num_of_certs: c_int,
+    pub certinfo: **curl_slist
+}
+
+/// Binary data handed to a *_BLOB option
+pub struct curl_blob {
+    pub data: *c_void,
+    pub len: size_t,
+    pub flags: c_uint
+}
+
+/// Tell libcurl to copy the blob's data rather than keep the pointer
+pub static CURL_BLOB_COPY: c_uint = 1;
+
 pub type CURL = c_void;
+pub type CURLM = c_void;
+
+/// The kind of message read from a multi handle
+pub enum CURLMSG {
+    CURLMSG_NONE, /* first, not used */
+    CURLMSG_DONE, /* This easy handle has completed. 'result' contains
+                     the CURLcode of the transfer */
+    CURLMSG_LAST  /* last, not used */
+}
+
+pub type CURLSH = c_void;
+
+/// Options for curl_share_setopt
+pub enum CURLSHoption {
+    CURLSHOPT_NONE,       /* don't use */
+    CURLSHOPT_SHARE,      /* specify a data type to share */
+    CURLSHOPT_UNSHARE,    /* specify which data type to stop sharing */
+    CURLSHOPT_LOCKFUNC,   /* pass in a 'curl_lock_function' pointer */
+    CURLSHOPT_UNLOCKFUNC, /* pass in a 'curl_unlock_function' pointer */
+    CURLSHOPT_USERDATA,   /* pass in a user data pointer used in the lock/unlock
+                             callback functions */
+    CURLSHOPT_LAST        /* never use */
+}
+
+/// The kinds of data a share handle protects with its lock callbacks
+pub enum curl_lock_data {
+    CURL_LOCK_DATA_NONE = 0,
+    CURL_LOCK_DATA_SHARE,
+    CURL_LOCK_DATA_COOKIE,
+    CURL_LOCK_DATA_DNS,
+    CURL_LOCK_DATA_SSL_SESSION,
+    CURL_LOCK_DATA_CONNECT,
+    CURL_LOCK_DATA_LAST
+}
+
+pub type curl_mime = c_void;
+pub type curl_mimepart = c_void;
+
+/// A message read from a multi handle with curl_multi_info_read.
+/// In C the result is a union with a void pointer; only the result is used.
+pub struct CURLMsg {
+    pub msg: CURLMSG,
+    pub easy_handle: *CURL,
+    pub result: CURLcode
+}
 
 #[link(name = "curl")]
 extern {
@@ -89,6 +198,36 @@ extern {
     pub fn curl_easy_strerror(err: CURLcode) -> *c_char;
     pub fn curl_easy_unescape(curl: *CURL, url: *c_char, inlength: c_int, outlength: *c_int) -> *c_char;
     
+    /* Multi interface */
+    pub fn curl_multi_add_handle(multi_handle: *CURLM, curl_handle: *CURL) -> CURLMcode;
+    pub fn curl_multi_cleanup(multi_handle: *CURLM) -> CURLMcode;
+    pub fn curl_multi_info_read(multi_handle: *CURLM, msgs_in_queue: *mut c_int) -> *CURLMsg;
+    pub fn curl_multi_init() -> *CURLM;
+    pub fn curl_multi_perform(multi_handle: *CURLM, running_handles: *mut c_int) -> CURLMcode;
+    pub fn curl_multi_remove_handle(multi_handle: *CURLM, curl_handle: *CURL) -> CURLMcode;
+    pub fn curl_multi_strerror(err: CURLMcode) -> *c_char;
+    pub fn curl_multi_timeout(multi_handle: *CURLM, milliseconds: *mut c_long) -> CURLMcode;
+    pub fn curl_multi_wait(multi_handle: *CURLM, extra_fds: *c_void, extra_nfds: c_uint,
+                           timeout_ms: c_int, ret: *mut c_int) -> CURLMcode;
+
+    /* Share interface */
+    pub fn curl_share_cleanup(share: *CURLSH) -> CURLSHcode;
+    pub fn curl_share_init() -> *CURLSH;
+    pub fn curl_share_setopt(share: *CURLSH, option: CURLSHoption, val: *c_void) -> CURLSHcode;
+    pub fn curl_share_strerror(err: CURLSHcode) -> *c_char;
+
+    /* Mime interface */
+    pub fn curl_mime_addpart(mime: *curl_mime) -> *curl_mimepart;
+    pub fn curl_mime_data(part: *curl_mimepart, data: *c_char, datasize: size_t) -> CURLcode;
+    pub fn curl_mime_data_cb(part: *curl_mimepart, datasize: i64, readfunc: *c_void, seekfunc: *c_void,
+                             freefunc: *c_void, arg: *c_void) -> CURLcode;
+    pub fn curl_mime_filedata(part: *curl_mimepart, filename: *c_char) -> CURLcode;
+    pub fn curl_mime_filename(part: *curl_mimepart, filename: *c_char) -> CURLcode;
+    pub fn curl_mime_free(mime: *curl_mime) -> c_void;
+    pub fn curl_mime_init(easy: *CURL) -> *curl_mime;
+    pub fn curl_mime_name(part: *curl_mimepart, name: *c_char) -> CURLcode;
+    pub fn curl_mime_type(part: *curl_mimepart, mimetype: *c_char) -> CURLcode;
+
     /* Utility */
     pub fn curl_free(ptr: *c_char) -> c_void;
 
@@ -0,0 +1,108 @@
+use std::fmt;
+
+use curl::easy_strerror;
+use curl::code;
+
+/// The result of a curl operation
+pub type CurlResult<T> = Result<T, CurlError>;
+
+/// An error returned by a curl operation, with as much detail about
+/// the failure as curl could provide
+#[deriving(Clone, PartialEq)]
+pub struct CurlError {
+    /// The code curl returned
+    pub code: code::CURLcode,
+    /// The detailed message left in the handle's ERRORBUFFER, or the
+    /// generic description of the code when curl did not leave one
+    pub message: String,
+    /// The OS errno of the failed connect, if any (CURLINFO_OS_ERRNO)
+    pub os_errno: Option<i64>,
+    /// The URL the handle was using when it failed
+    pub url: Option<String>
+}
+
+impl CurlError {
+    /// Create an error carrying only a code and its generic description
+    /// # Arguments
+    /// * `c` - the code curl returned
+    /// # Example
+    /// ~~~ {.rust}
+    /// let err = CurlError::new(code::CURLE_OPERATION_TIMEDOUT);
+    /// assert!(err.is_timeout());
+    /// ~~~
+    pub fn new(c: code::CURLcode) -> CurlError {
+        CurlError { code: c, message: easy_strerror(c), os_errno: None, url: None }
+    }
+
+    /// The generic description of the error code, see `easy_strerror`
+    pub fn description(&self) -> String {
+        easy_strerror(self.code)
+    }
+
+    /// True if the operation ran out of time
+    pub fn is_timeout(&self) -> bool {
+        self.code == code::CURLE_OPERATION_TIMEDOUT
+    }
+
+    /// True if the host or proxy name could not be resolved
+    pub fn is_dns(&self) -> bool {
+        match self.code {
+            code::CURLE_COULDNT_RESOLVE_HOST | code::CURLE_COULDNT_RESOLVE_PROXY => true,
+            _ => false
+        }
+    }
+
+    /// True if the connection to the host or proxy could not be made
+    pub fn is_connect(&self) -> bool {
+        self.code == code::CURLE_COULDNT_CONNECT
+    }
+
+    /// True if the failure happened in the SSL/TLS layer, including
+    /// certificate verification failures
+    pub fn is_tls(&self) -> bool {
+        match self.code {
+            code::CURLE_SSL_CONNECT_ERROR | code::CURLE_PEER_FAILED_VERIFICATION |
+            code::CURLE_SSL_ENGINE_NOTFOUND | code::CURLE_SSL_ENGINE_SETFAILED |
+            code::CURLE_SSL_CERTPROBLEM | code::CURLE_SSL_CIPHER | code::CURLE_SSL_CACERT |
+            code::CURLE_USE_SSL_FAILED | code::CURLE_SSL_ENGINE_INITFAILED |
+            code::CURLE_SSL_CACERT_BADFILE | code::CURLE_SSL_SHUTDOWN_FAILED |
+            code::CURLE_SSL_CRL_BADFILE | code::CURLE_SSL_ISSUER_ERROR => true,
+            _ => false
+        }
+    }
+}
+
+impl fmt::Show for CurlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{} (curl code {})", self.message, self.code as int));
+
+        match self.url {
+            Some(ref url) => try!(write!(f, " for {}", url)),
+            None => { ; }
+        }
+
+        match self.os_errno {
+            Some(errno) => write!(f, ", errno {}", errno),
+            None => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use curl::code;
+
+    #[test]
+    fn test_classification() {
+        assert!(CurlError::new(code::CURLE_OPERATION_TIMEDOUT).is_timeout());
+        assert!(CurlError::new(code::CURLE_COULDNT_RESOLVE_HOST).is_dns());
+        assert!(CurlError::new(code::CURLE_COULDNT_RESOLVE_PROXY).is_dns());
+        assert!(CurlError::new(code::CURLE_COULDNT_CONNECT).is_connect());
+        assert!(CurlError::new(code::CURLE_PEER_FAILED_VERIFICATION).is_tls());
+        assert!(CurlError::new(code::CURLE_SSL_CACERT).is_tls());
+
+        let err = CurlError::new(code::CURLE_WRITE_ERROR);
+        assert!(!err.is_timeout() && !err.is_dns() && !err.is_connect() && !err.is_tls());
+    }
+}
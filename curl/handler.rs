@@ -0,0 +1,358 @@
+use libc::{size_t,c_char,c_int,c_double,c_void};
+use std::any::{Any,AnyRefExt};
+use std::mem;
+use std::ptr;
+use std::rt::unwind::try;
+use std::slice::raw::{buf_as_slice,mut_buf_as_slice};
+
+use curl::{Curl,opt,code};
+use curl::curl_ll::{CURL,CURL_READFUNC_ABORT};
+use curl::error::{CurlError,CurlResult};
+
+/// The kind of data given to a debug handler (curl_infotype)
+#[deriving(Show, Clone, PartialEq)]
+pub enum DebugInfo {
+    DebugText,
+    DebugHeaderIn,
+    DebugHeaderOut,
+    DebugDataIn,
+    DebugDataOut,
+    DebugSslDataIn,
+    DebugSslDataOut
+}
+
+/// A set of Rust closures called by curl while it performs a transfer.
+/// Each handler is optional; events without a handler get curl's usual behaviour.
+///
+/// A handler that fails is caught before the failure can unwind into curl:
+/// the transfer is aborted and `Curl::easy_perform_with` returns an error instead.
+///
+/// # Example
+/// ~~~ {.rust}
+/// let mut body = vec![];
+/// let mut handlers = CurlHandlers::new()
+///     .write(|data| { body.push_all(data); data.len() })
+///     .progress(|dltotal, dlnow, _, _| { println!("{}/{}", dlnow, dltotal); true });
+///
+/// let curl = Curl::new();
+/// curl.easy_setopt(URL("http://www.google.com"));
+/// curl.easy_perform_with(&mut handlers);
+/// ~~~
+pub struct CurlHandlers<'a> {
+    write: Option<|&[u8]|: 'a -> uint>,
+    header: Option<|&[u8]|: 'a -> uint>,
+    read: Option<|&mut [u8]|: 'a -> uint>,
+    progress: Option<|f64, f64, f64, f64|: 'a -> bool>,
+    debug: Option<|DebugInfo, &[u8]|: 'a>,
+    failure: Option<Box<Any:Send>>,
+    // options attach set on the handle, for detach to reset
+    installed: Vec<opt::CURLoption>
+}
+
+impl<'a> CurlHandlers<'a> {
+    /// Return an empty set of handlers
+    pub fn new() -> CurlHandlers<'a> {
+        CurlHandlers { write: None, header: None, read: None, progress: None, debug: None,
+                       failure: None, installed: vec![] }
+    }
+
+    /// Called with each chunk of the response body. Must return the number
+    /// of bytes taken; anything other than the chunk length aborts the transfer.
+    pub fn write(mut self, f: |&[u8]|: 'a -> uint) -> CurlHandlers<'a> {
+        self.write = Some(f);
+        self
+    }
+
+    /// Called with each complete header line, including the status line
+    /// and the trailing CRLF. Returns like the write handler.
+    pub fn header(mut self, f: |&[u8]|: 'a -> uint) -> CurlHandlers<'a> {
+        self.header = Some(f);
+        self
+    }
+
+    /// Called to fill the buffer with upload data. Must return the number
+    /// of bytes written to the buffer, 0 once the upload is complete.
+    pub fn read(mut self, f: |&mut [u8]|: 'a -> uint) -> CurlHandlers<'a> {
+        self.read = Some(f);
+        self
+    }
+
+    /// Called periodically with (download total, downloaded, upload total, uploaded)
+    /// byte counts. Returning false aborts the transfer.
+    pub fn progress(mut self, f: |f64, f64, f64, f64|: 'a -> bool) -> CurlHandlers<'a> {
+        self.progress = Some(f);
+        self
+    }
+
+    /// Called with everything curl would print in verbose mode. Installing
+    /// this handler turns verbose mode on for the transfer.
+    pub fn debug(mut self, f: |DebugInfo, &[u8]|: 'a) -> CurlHandlers<'a> {
+        self.debug = Some(f);
+        self
+    }
+
+    /// Point curl's callbacks for the installed handlers at this set.
+    /// # Safety Note
+    /// The handlers must not move and must outlive every transfer on the
+    /// handle until `detach` is called. `Curl::easy_perform_with` takes care
+    /// of this for a single transfer.
+    pub unsafe fn attach(&mut self, curl: &Curl) -> CurlResult<()> {
+        let data = self as *mut CurlHandlers as *c_void;
+
+        if self.write.is_some() {
+            try!(self.install(curl, opt::WRITEFUNCTION, mem::transmute(c_write_handler)));
+            try!(self.install(curl, opt::WRITEDATA, data));
+        }
+        if self.header.is_some() {
+            try!(self.install(curl, opt::HEADERFUNCTION, mem::transmute(c_header_handler)));
+            try!(self.install(curl, opt::HEADERDATA, data));
+        }
+        if self.read.is_some() {
+            try!(self.install(curl, opt::READFUNCTION, mem::transmute(c_read_handler)));
+            // INFILE is the old name of READDATA
+            try!(self.install(curl, opt::INFILE, data));
+        }
+        if self.progress.is_some() {
+            try!(self.install(curl, opt::PROGRESSFUNCTION, mem::transmute(c_progress_handler)));
+            try!(self.install(curl, opt::PROGRESSDATA, data));
+            try!(curl.easy_setopt_bool(opt::NOPROGRESS, false));
+            self.installed.push(opt::NOPROGRESS);
+        }
+        if self.debug.is_some() {
+            try!(self.install(curl, opt::DEBUGFUNCTION, mem::transmute(c_debug_handler)));
+            try!(self.install(curl, opt::DEBUGDATA, data));
+            try!(curl.easy_setopt_bool(opt::VERBOSE, true));
+            self.installed.push(opt::VERBOSE);
+        }
+        Ok(())
+    }
+
+    /// Stop curl from calling into this set, resetting only the options
+    /// `attach` set. Body data is discarded and uploads are empty until new
+    /// callbacks are set. Every option is reset even if one fails, and the
+    /// first failure is returned.
+    pub fn detach(&mut self, curl: &Curl) -> CurlResult<()> {
+        let mut res = Ok(());
+
+        for &option in self.installed.iter() {
+            let reset = unsafe {
+                match option {
+                    opt::WRITEFUNCTION => curl.easy_setopt_ptr(option, mem::transmute(c_discard_handler)),
+                    opt::READFUNCTION => curl.easy_setopt_ptr(option, mem::transmute(c_empty_read_handler)),
+                    opt::NOPROGRESS => curl.easy_setopt_bool(option, true),
+                    opt::VERBOSE => curl.easy_setopt_bool(option, false),
+                    _ => curl.easy_setopt_ptr(option, ptr::null())
+                }
+            };
+            res = res.and(reset);
+        }
+        self.installed.clear();
+        res
+    }
+
+    /// Sets a callback or its data pointer, remembering it for `detach`
+    unsafe fn install(&mut self, curl: &Curl, option: opt::CURLoption, val: *c_void) -> CurlResult<()> {
+        try!(curl.easy_setopt_ptr(option, val));
+        self.installed.push(option);
+        Ok(())
+    }
+
+    /// If one of the handlers failed, returns the error describing it.
+    /// Handlers are not called again after a failure.
+    pub fn failure(&self) -> Option<CurlError> {
+        self.failure.as_ref().map(|cause| {
+            let reason = match cause.as_ref::<&'static str>() {
+                Some(s) => s.to_string(),
+                None => match cause.as_ref::<String>() {
+                    Some(s) => s.clone(),
+                    None => "unknown failure".to_string()
+                }
+            };
+
+            let mut err = CurlError::new(code::CURLE_ABORTED_BY_CALLBACK);
+            err.message = format!("handler failed: {}", reason);
+            err
+        })
+    }
+}
+
+impl Curl {
+    /// Perform the request, calling the given closures as curl produces
+    /// and consumes data. The handlers are only installed for this transfer.
+    /// # Arguments
+    /// * `handlers` -  the closures to call
+    /// # Example
+    /// ~~~ {.rust}
+    /// let mut body = vec![];
+    /// let curl = Curl::new();
+    /// curl.easy_setopt(URL("http://www.google.com"));
+    /// curl.easy_perform_with(&mut CurlHandlers::new().write(|data| { body.push_all(data); data.len() }));
+    /// ~~~
+    pub fn easy_perform_with<'a>(&self, handlers: &mut CurlHandlers<'a>) -> CurlResult<()> {
+        // detach also undoes whatever a failed attach managed to set
+        let res = unsafe { handlers.attach(self) }.and_then(|()| self.easy_perform());
+        let detached = handlers.detach(self);
+
+        match handlers.failure() {
+            Some(err) => Err(err),
+            None => res.and(detached)
+        }
+    }
+}
+
+/// Runs `f` on the handlers behind `ptr`, catching any failure so it cannot
+/// unwind into curl. Returns None if `f` failed or a handler failed before.
+fn guard<T>(ptr: *c_void, f: |&mut CurlHandlers| -> T) -> Option<T> {
+    let handlers: &mut CurlHandlers = unsafe { mem::transmute(ptr) };
+    if handlers.failure.is_some() {
+        return None;
+    }
+
+    let mut ret = None;
+    let res = unsafe {
+        let inner: &mut CurlHandlers = mem::transmute(ptr);
+        try(|| { ret = Some(f(inner)); })
+    };
+
+    match res {
+        Ok(()) => ret,
+        Err(cause) => {
+            handlers.failure = Some(cause);
+            None
+        }
+    }
+}
+
+extern "C" fn c_write_handler(data: *u8, size: size_t, nmemb: size_t, user_data: *c_void) -> size_t {
+    let len = (size * nmemb) as uint;
+    guard(user_data, |h| unsafe {
+        buf_as_slice(data, len, |buf| match h.write {
+            Some(ref mut f) => (*f)(buf),
+            None => len
+        })
+    }).unwrap_or(0) as size_t
+}
+
+extern "C" fn c_header_handler(data: *u8, size: size_t, nmemb: size_t, user_data: *c_void) -> size_t {
+    let len = (size * nmemb) as uint;
+    guard(user_data, |h| unsafe {
+        buf_as_slice(data, len, |buf| match h.header {
+            Some(ref mut f) => (*f)(buf),
+            None => len
+        })
+    }).unwrap_or(0) as size_t
+}
+
+extern "C" fn c_read_handler(buffer: *mut u8, size: size_t, nmemb: size_t, user_data: *c_void) -> size_t {
+    let len = (size * nmemb) as uint;
+    match guard(user_data, |h| unsafe {
+        mut_buf_as_slice(buffer, len, |buf| match h.read {
+            Some(ref mut f) => (*f)(buf),
+            None => 0
+        })
+    }) {
+        Some(n) => n as size_t,
+        None => CURL_READFUNC_ABORT
+    }
+}
+
+extern "C" fn c_progress_handler(user_data: *c_void, dltotal: c_double, dlnow: c_double,
+                                 ultotal: c_double, ulnow: c_double) -> c_int {
+    match guard(user_data, |h| match h.progress {
+        Some(ref mut f) => (*f)(dltotal as f64, dlnow as f64, ultotal as f64, ulnow as f64),
+        None => true
+    }) {
+        Some(true) => 0,
+        _ => 1
+    }
+}
+
+extern "C" fn c_debug_handler(_handle: *CURL, kind: c_int, data: *c_char, size: size_t,
+                              user_data: *c_void) -> c_int {
+    let info = match kind {
+        1 => DebugHeaderIn,
+        2 => DebugHeaderOut,
+        3 => DebugDataIn,
+        4 => DebugDataOut,
+        5 => DebugSslDataIn,
+        6 => DebugSslDataOut,
+        _ => DebugText
+    };
+
+    guard(user_data, |h| unsafe {
+        buf_as_slice(data as *u8, size as uint, |buf| match h.debug {
+            Some(ref mut f) => (*f)(info.clone(), buf),
+            None => { ; }
+        })
+    });
+    0
+}
+
+/// Installed in place of a detached write handler, so curl never writes to stale user data
+extern "C" fn c_discard_handler(_data: *u8, size: size_t, nmemb: size_t, _user_data: *c_void) -> size_t {
+    size * nmemb
+}
+
+/// Installed in place of a detached read handler, so curl never reads from stale user data
+extern "C" fn c_empty_read_handler(_buffer: *mut u8, _size: size_t, _nmemb: size_t, _user_data: *c_void) -> size_t {
+    0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use curl::{Curl,URL,code};
+
+    #[test]
+    fn test_perform_with_closures() {
+        let curl = Curl::new();
+        let mut body = vec![];
+        let mut header_lines = 0u;
+        let mut progress_calls = 0u;
+
+        curl.easy_setopt(URL("http://www.google.com")).unwrap();
+        {
+            let mut handlers = CurlHandlers::new()
+                .write(|data| { body.push_all(data); data.len() })
+                .header(|line| { header_lines += 1; line.len() })
+                .progress(|_, _, _, _| { progress_calls += 1; true });
+
+            curl.easy_perform_with(&mut handlers).unwrap();
+        }
+
+        assert!(!body.is_empty());
+        assert!(header_lines > 1);
+        assert!(progress_calls > 0);
+    }
+
+    #[test]
+    fn test_failing_handler_is_caught() {
+        let curl = Curl::new();
+
+        curl.easy_setopt(URL("file:///etc/passwd")).unwrap();
+        let mut handlers = CurlHandlers::new().write(|_| fail!("boom"));
+
+        match curl.easy_perform_with(&mut handlers) {
+            Ok(()) => { fail!("a failing handler should abort the transfer"); }
+            Err(err) => {
+                assert!(err.code == code::CURLE_ABORTED_BY_CALLBACK);
+                assert!(err.message.as_slice().contains("boom"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_aborting_progress_handler() {
+        let curl = Curl::new();
+
+        curl.easy_setopt(URL("http://www.google.com")).unwrap();
+        let mut handlers = CurlHandlers::new()
+            .write(|data| data.len())
+            .progress(|_, _, _, _| false);
+
+        match curl.easy_perform_with(&mut handlers) {
+            Ok(()) => { fail!("returning false from progress should abort"); }
+            Err(err) => { assert!(err.code == code::CURLE_ABORTED_BY_CALLBACK); }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use libc::{size_t,c_char,c_void};
+use std::io::EndOfFile;
+use std::mem;
+use std::ptr;
+use std::rt::unwind::try;
+use std::slice::raw::mut_buf_as_slice;
+
+use curl::{Curl,code,checked_c_str};
+use curl::curl_ll::*;
+use curl::error::{CurlError,CurlResult};
+
+/// Wrapper over curl_mime, the body of a multipart/form-data POST. It is
+/// built for one handle and handed to it with `Curl::easy_setopt_mime`,
+/// which keeps it until the handle is reset or dropped.
+///
+/// # Example
+/// ~~~ {.rust}
+/// let curl = Curl::new();
+/// let mut mime = CurlMime::new(&curl).unwrap();
+/// {
+///     let part = mime.add_part();
+///     part.name("avatar");
+///     part.file(&Path::new("me.png"));
+///     part.mime_type("image/png");
+/// }
+/// curl.easy_setopt(URL("http://example.com/upload"));
+/// curl.easy_setopt_mime(mime);
+/// curl.easy_perform();
+/// ~~~
+pub struct CurlMime {
+    mime: *curl_mime,
+    // the sources of streamed parts; curl holds pointers to them
+    sources: Vec<Box<ReaderSource>>
+}
+
+/// One part of a CurlMime
+pub struct MimePart<'a> {
+    mime: &'a mut CurlMime,
+    part: *curl_mimepart
+}
+
+/// What a streamed part reads from
+struct ReaderSource {
+    reader: Box<Reader:Send>
+}
+
+impl CurlMime {
+    /// Return an empty mime structure for `curl`. Fails with
+    /// CURLE_OUT_OF_MEMORY if libcurl can't allocate one.
+    pub fn new(curl: &Curl) -> CurlResult<CurlMime> {
+        let mime = unsafe { curl_mime_init(curl.curl) };
+        if mime.is_null() {
+            return Err(CurlError::new(code::CURLE_OUT_OF_MEMORY));
+        }
+
+        Ok(CurlMime { mime: mime, sources: vec![] })
+    }
+
+    /// Append a new, empty part
+    pub fn add_part<'a>(&'a mut self) -> MimePart<'a> {
+        let part = unsafe { curl_mime_addpart(self.mime) };
+        MimePart { mime: self, part: part }
+    }
+
+    /// The raw mime handle, for CURLOPT_MIMEPOST
+    pub fn handle(&self) -> *curl_mime {
+        self.mime
+    }
+}
+
+impl<'a> MimePart<'a> {
+    /// The form field name of the part
+    pub fn name(&self, name: &str) -> CurlResult<()> {
+        try!(checked_c_str(name)).with_ref(|s| check(unsafe { curl_mime_name(self.part, s) }))
+    }
+
+    /// The file name given in the part's Content-Disposition
+    pub fn filename(&self, filename: &str) -> CurlResult<()> {
+        try!(checked_c_str(filename)).with_ref(|s| check(unsafe { curl_mime_filename(self.part, s) }))
+    }
+
+    /// The part's Content-Type
+    pub fn mime_type(&self, mime_type: &str) -> CurlResult<()> {
+        try!(checked_c_str(mime_type)).with_ref(|s| check(unsafe { curl_mime_type(self.part, s) }))
+    }
+
+    /// Send `data` as the part's content; it is copied
+    pub fn data(&self, data: &[u8]) -> CurlResult<()> {
+        check(unsafe { curl_mime_data(self.part, data.as_ptr() as *c_char, data.len() as size_t) })
+    }
+
+    /// Send the contents of the file at `path`, read during the transfer.
+    /// The part's file name is set to the file's base name.
+    pub fn file(&self, path: &Path) -> CurlResult<()> {
+        path.with_c_str(|s| check(unsafe { curl_mime_filedata(self.part, s) }))
+    }
+
+    /// Send what `reader` yields, read during the transfer. Without a
+    /// `size` the request body is sent with chunked encoding.
+    pub fn reader(&mut self, reader: Box<Reader:Send>, size: Option<u64>) -> CurlResult<()> {
+        let source = box ReaderSource { reader: reader };
+        let arg = &*source as *ReaderSource as *c_void;
+        let size = match size {
+            Some(s) => s as i64,
+            None => -1
+        };
+
+        unsafe {
+            try!(check(curl_mime_data_cb(self.part, size, mem::transmute(c_mime_read_fn),
+                                         ptr::null(), ptr::null(), arg)));
+        }
+        self.mime.sources.push(source);
+        Ok(())
+    }
+}
+
+impl Drop for CurlMime {
+    fn drop(&mut self) {
+        unsafe {
+            curl_mime_free(self.mime);
+        }
+    }
+}
+
+fn check(c: code::CURLcode) -> CurlResult<()> {
+    match c {
+        code::CURLE_OK => Ok(()),
+        c => Err(CurlError::new(c))
+    }
+}
+
+/// C read callback of a streamed part; user_data is its ReaderSource.
+/// A reader that fails, or fails!, aborts the transfer.
+extern "C" fn c_mime_read_fn(buffer: *mut u8, size: size_t, nitems: size_t, user_data: *c_void) -> size_t {
+    let len = (size * nitems) as uint;
+
+    let mut res = Ok(0);
+    let caught = unsafe {
+        let source: &mut ReaderSource = mem::transmute(user_data);
+        try(|| {
+            res = mut_buf_as_slice(buffer, len, |buf| source.reader.read(buf));
+        })
+    };
+
+    match (caught, res) {
+        (Ok(()), Ok(n)) => n as size_t,
+        (Ok(()), Err(ref e)) if e.kind == EndOfFile => 0,
+        _ => CURL_READFUNC_ABORT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use curl::*;
+    use curl::callback::SimpleCurlByteBuffer;
+    use std::io::MemReader;
+    use std::str::from_utf8;
+
+    #[test]
+    fn test_mime_post() {
+        let curl = Curl::new();
+        let mut mime = CurlMime::new(&curl).unwrap();
+        {
+            let part = mime.add_part();
+            part.name("greeting").unwrap();
+            part.data(bytes!("hello")).unwrap();
+        }
+        {
+            let mut part = mime.add_part();
+            part.name("upload").unwrap();
+            part.filename("notes.txt").unwrap();
+            part.mime_type("text/plain").unwrap();
+            part.reader(box MemReader::new(Vec::from_slice(bytes!("streamed"))), None).unwrap();
+        }
+
+        let buf = SimpleCurlByteBuffer::new();
+        curl.easy_setopt(URL("http://httpbin.org/post")).unwrap();
+        curl.easy_setopt_mime(mime).unwrap();
+        curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &buf).unwrap();
+        assert!(curl.easy_perform().is_ok());
+
+        let echoed = from_utf8(buf.data.as_slice()).unwrap();
+        assert!(echoed.contains("\"greeting\": \"hello\""));
+        assert!(echoed.contains("\"upload\": \"streamed\""));
+    }
+
+    #[test]
+    fn test_nul_in_part_name_is_rejected() {
+        use curl::code;
+
+        let curl = Curl::new();
+        let mut mime = CurlMime::new(&curl).unwrap();
+        let part = mime.add_part();
+
+        let err = part.name("a\0b").unwrap_err();
+        assert!(err.code == code::CURLE_BAD_FUNCTION_ARGUMENT);
+        assert!(part.filename("a\0b.txt").is_err());
+        assert!(part.mime_type("text/plain\0").is_err());
+    }
+}
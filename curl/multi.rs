@@ -0,0 +1,302 @@
+use libc::{c_int,c_long};
+use std::fmt;
+use std::ptr;
+
+use curl::{Curl,code};
+use curl::curl_ll::*;
+use curl::error::CurlResult;
+use curl::handler::CurlHandlers;
+
+/// The result of a multi interface operation
+pub type CurlMultiResult<T> = Result<T, CurlMultiError>;
+
+/// An error returned by one of the curl_multi* functions. Errors of the
+/// individual transfers are reported by `CurlMulti::info_read` instead.
+#[deriving(Clone, PartialEq)]
+pub struct CurlMultiError {
+    pub code: code::CURLMcode,
+    pub message: String
+}
+
+impl CurlMultiError {
+    pub fn new(c: code::CURLMcode) -> CurlMultiError {
+        CurlMultiError { code: c, message: multi_strerror(c) }
+    }
+}
+
+impl fmt::Show for CurlMultiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (curl multi code {})", self.message, self.code as int)
+    }
+}
+
+/// Converts a curl::code::CURLMcode into its error string.
+pub fn multi_strerror(c: code::CURLMcode) -> String {
+    use std::str::raw::from_c_str;
+
+    unsafe {
+        from_c_str(curl_multi_strerror(c))
+    }
+}
+
+/// An easy handle added to a CurlMulti, with the handlers it calls
+struct Transfer<'a> {
+    curl: Curl,
+    // boxed so the address curl was given stays put
+    handlers: Box<CurlHandlers<'a>>
+}
+
+/// Wrapper over the CURLM multi handle, which drives many transfers
+/// at once from a single thread. It owns the easy handles added to it;
+/// each is identified by the id returned when it was added.
+///
+/// # Example
+/// ~~~ {.rust}
+/// let mut bodies = vec![vec![], vec![]];
+/// let mut multi = CurlMulti::new().unwrap();
+///
+/// for (url, body) in ["http://a.example/", "http://b.example/"].iter().zip(bodies.mut_iter()) {
+///     let curl = Curl::new();
+///     curl.easy_setopt(URL(*url));
+///     multi.add_handle_with(curl, CurlHandlers::new().write(|data| { body.push_all(data); data.len() }));
+/// }
+///
+/// for (id, res) in multi.run().unwrap().move_iter() {
+///     println!("transfer {} finished: {}", id, res.is_ok());
+/// }
+/// ~~~
+pub struct CurlMulti<'a> {
+    multi: *CURLM,
+    transfers: Vec<Option<Transfer<'a>>>
+}
+
+impl<'a> CurlMulti<'a> {
+    /// Return a new, empty CurlMulti object, or CURLM_OUT_OF_MEMORY if
+    /// libcurl could not make the multi handle
+    pub fn new() -> CurlMultiResult<CurlMulti<'a>> {
+        let multi = unsafe { curl_multi_init() };
+        if multi.is_null() {
+            return Err(CurlMultiError::new(code::CURLM_OUT_OF_MEMORY));
+        }
+        Ok(CurlMulti { multi: multi, transfers: vec![] })
+    }
+
+    /// Add an easy handle whose callbacks are already set up
+    /// # Arguments
+    /// * `curl` -  the handle, with at least its URL set
+    pub fn add_handle(&mut self, curl: Curl) -> CurlMultiResult<uint> {
+        self.add_handle_with(curl, CurlHandlers::new())
+    }
+
+    /// Add an easy handle together with the handlers to call during its
+    /// transfer. Returns the id that identifies the handle from now on.
+    /// # Arguments
+    /// * `curl` -  the handle, with at least its URL set
+    /// * `handlers` -  the closures to call for this transfer
+    pub fn add_handle_with(&mut self, curl: Curl, handlers: CurlHandlers<'a>) -> CurlMultiResult<uint> {
+        let mut transfer = Transfer { curl: curl, handlers: box handlers };
+
+        unsafe {
+            match transfer.handlers.attach(&transfer.curl) {
+                Ok(()) => { ; }
+                Err(_) => { return Err(CurlMultiError::new(code::CURLM_BAD_EASY_HANDLE)); }
+            }
+            transfer.curl.clear_errbuf();
+            try!(check(curl_multi_add_handle(self.multi, transfer.curl.curl)));
+        }
+
+        self.transfers.push(Some(transfer));
+        Ok(self.transfers.len() - 1)
+    }
+
+    /// Remove a handle, finished or not, and give it back
+    /// # Arguments
+    /// * `id` -    the id returned when the handle was added
+    pub fn remove_handle(&mut self, id: uint) -> Option<Curl> {
+        if id >= self.transfers.len() {
+            return None;
+        }
+
+        let taken = self.transfers.get_mut(id).take();
+        taken.map(|mut transfer| {
+            unsafe {
+                curl_multi_remove_handle(self.multi, transfer.curl.curl);
+            }
+            // only fails for options this libcurl doesn't know, and attach
+            // already set every one of them on this handle
+            let _ = transfer.handlers.detach(&transfer.curl);
+            transfer.curl
+        })
+    }
+
+    /// The easy handle with the given id, e.g. to call easy_getinfo on it
+    pub fn get<'b>(&'b self, id: uint) -> Option<&'b Curl> {
+        match self.transfers.as_slice().get(id) {
+            Some(&Some(ref transfer)) => Some(&transfer.curl),
+            _ => None
+        }
+    }
+
+    /// Wrapper over curl_multi_perform, which does whatever work can be done
+    /// right now without blocking. Returns the number of transfers still running.
+    pub fn perform(&mut self) -> CurlMultiResult<uint> {
+        let mut running: c_int = 0;
+
+        loop {
+            match unsafe { curl_multi_perform(self.multi, &mut running) } {
+                code::CURLM_CALL_MULTI_PERFORM => { continue; }
+                c => { try!(check(c)); }
+            }
+            return Ok(running as uint);
+        }
+    }
+
+    /// Wrapper over curl_multi_wait, which blocks until there is activity on
+    /// one of the transfers or the timeout expires. Returns the number of
+    /// sockets with activity.
+    /// # Arguments
+    /// * `timeout_ms` -    the longest time to wait, in milliseconds
+    pub fn wait(&mut self, timeout_ms: uint) -> CurlMultiResult<uint> {
+        let mut numfds: c_int = 0;
+
+        unsafe {
+            try!(check(curl_multi_wait(self.multi, ptr::null(), 0, timeout_ms as c_int, &mut numfds)));
+        }
+        Ok(numfds as uint)
+    }
+
+    /// Wrapper over curl_multi_timeout: how long to wait before calling
+    /// perform again, or None if there is no timeout set
+    pub fn timeout(&self) -> CurlMultiResult<Option<uint>> {
+        let mut ms: c_long = -1;
+
+        unsafe {
+            try!(check(curl_multi_timeout(self.multi, &mut ms)));
+        }
+        Ok(match ms {
+            ms if ms < 0 => None,
+            ms => Some(ms as uint)
+        })
+    }
+
+    /// Wrapper over curl_multi_info_read. Returns the id and result of the
+    /// next finished transfer, or None when no more transfers have finished.
+    /// Finished handles stay added until removed.
+    pub fn info_read(&mut self) -> Option<(uint, CurlResult<()>)> {
+        let mut queued: c_int = 0;
+
+        loop {
+            let msg = unsafe { curl_multi_info_read(self.multi, &mut queued) };
+            if msg.is_null() {
+                return None;
+            }
+
+            let (kind, easy, result) = unsafe { ((*msg).msg as int, (*msg).easy_handle, (*msg).result) };
+            if kind != CURLMSG_DONE as int {
+                continue;
+            }
+
+            for (id, slot) in self.transfers.iter().enumerate() {
+                match *slot {
+                    Some(ref t) if t.curl.curl == easy => {
+                        let res = match t.handlers.failure() {
+                            Some(err) => Err(err),
+                            None => t.curl.check_transfer(result)
+                        };
+                        return Some((id, res));
+                    }
+                    _ => { ; }
+                }
+            }
+        }
+    }
+
+    /// Drive every added transfer to completion, returning the id and result
+    /// of each transfer in the order they finished
+    pub fn run(&mut self) -> CurlMultiResult<Vec<(uint, CurlResult<()>)>> {
+        let mut done = vec![];
+
+        loop {
+            let running = try!(self.perform());
+
+            loop {
+                match self.info_read() {
+                    Some(finished) => { done.push(finished); }
+                    None => { break; }
+                }
+            }
+
+            if running == 0 {
+                return Ok(done);
+            }
+            try!(self.wait(1000));
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for CurlMulti<'a> {
+    fn drop(&mut self) {
+        for id in range(0, self.transfers.len()) {
+            self.remove_handle(id);
+        }
+        unsafe {
+            curl_multi_cleanup(self.multi);
+        }
+    }
+}
+
+/// Turns a code returned by a multi function into a result
+fn check(c: code::CURLMcode) -> CurlMultiResult<()> {
+    match c {
+        code::CURLM_OK => Ok(()),
+        _ => Err(CurlMultiError::new(c))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use curl::{Curl,URL,code};
+    use curl::handler::CurlHandlers;
+
+    #[test]
+    fn test_concurrent_file_transfers() {
+        let mut passwd = vec![];
+        let mut hosts = vec![];
+
+        {
+            let mut multi = CurlMulti::new().unwrap();
+
+            let curl = Curl::new();
+            curl.easy_setopt(URL("file:///etc/passwd")).unwrap();
+            let a = multi.add_handle_with(curl, CurlHandlers::new().write(|d| { passwd.push_all(d); d.len() })).unwrap();
+
+            let curl = Curl::new();
+            curl.easy_setopt(URL("file:///etc/hosts")).unwrap();
+            let b = multi.add_handle_with(curl, CurlHandlers::new().write(|d| { hosts.push_all(d); d.len() })).unwrap();
+
+            let curl = Curl::new();
+            curl.easy_setopt(URL("file:///nonexistent/file")).unwrap();
+            let c = multi.add_handle_with(curl, CurlHandlers::new().write(|d| d.len())).unwrap();
+
+            let mut done = multi.run().unwrap();
+            done.sort_by(|&(x, _), &(y, _)| x.cmp(&y));
+
+            assert_eq!(done.len(), 3);
+            assert!(*done.get(0) == (a, Ok(())));
+            assert!(*done.get(1) == (b, Ok(())));
+
+            let &(id, ref res) = done.get(2);
+            assert_eq!(id, c);
+            assert!(res.as_ref().unwrap_err().code == code::CURLE_FILE_COULDNT_READ_FILE);
+
+            assert!(multi.remove_handle(a).is_some());
+            assert!(multi.remove_handle(a).is_none());
+            assert!(multi.get(b).is_some());
+        }
+
+        assert!(!passwd.is_empty());
+        assert!(!hosts.is_empty());
+    }
+}
@@ -1,3 +1,47 @@
+use std::fmt;
+
+/// Option numbers are offset by the type of value the option takes
+pub static LONG: int = 0;
+pub static OBJECTPOINT: int = 10000;
+pub static FUNCTIONPOINT: int = 20000;
+pub static OFF_T: int = 30000;
+pub static BLOB: int = 40000;
+
+/// The type of value a CURLoption takes
+#[deriving(PartialEq, Eq, Clone)]
+pub enum OptionType {
+    LongType,
+    ObjectPointType,
+    FunctionPointType,
+    OffTType,
+    BlobType
+}
+
+impl fmt::Show for OptionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            LongType => "long",
+            ObjectPointType => "object pointer",
+            FunctionPointType => "function pointer",
+            OffTType => "off_t",
+            BlobType => "blob"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl CURLoption {
+    /// The type of value this option takes, read from its number
+    pub fn option_type(self) -> OptionType {
+        let n = self as int;
+
+        if n >= BLOB { BlobType }
+        else if n >= OFF_T { OffTType }
+        else if n >= FUNCTIONPOINT { FunctionPointType }
+        else if n >= OBJECTPOINT { ObjectPointType }
+        else { LongType }
+    }
+}
 
 pub enum CURLoption {
     /* This is the FILE * or void * the regular output should be written to. */
@@ -735,6 +779,24 @@ pub enum CURLoption {
     /* Enable/disable SASL initial response */
     SASL_IR = 218,
 
+    /* The public key the server's certificate must have: a file, or
+         "sha256//<base64 hash>" pins separated by ";" */
+    PINNEDPUBLICKEY = 10230,
+
+    /* Post a MIME structure built with the curl_mime_* functions, see curl::mime */
+    MIMEPOST = 10269,
+
+    /* Client certificate, private key and CA certificates given in memory
+         as a struct curl_blob instead of by file name */
+    SSLCERT_BLOB = 40291,
+    SSLKEY_BLOB = 40292,
+    PROXY_SSLCERT_BLOB = 40293,
+    PROXY_SSLKEY_BLOB = 40294,
+    ISSUERCERT_BLOB = 40295,
+    PROXY_ISSUERCERT_BLOB = 40296,
+    CAINFO_BLOB = 40309,
+    PROXY_CAINFO_BLOB = 40310,
+
     CURLOPT_LASTENTRY = 0 /* the last unused */
 }
 
@@ -0,0 +1,178 @@
+use libc::{c_int,c_void};
+use std::fmt;
+use std::mem;
+use std::rt::mutex::NativeMutex;
+
+use curl::code;
+use curl::curl_ll::*;
+
+/// The result of a share interface operation
+pub type CurlShareResult<T> = Result<T, CurlShareError>;
+
+/// An error returned by one of the curl_share* functions
+#[deriving(Clone, PartialEq)]
+pub struct CurlShareError {
+    pub code: code::CURLSHcode,
+    pub message: String
+}
+
+impl CurlShareError {
+    pub fn new(c: code::CURLSHcode) -> CurlShareError {
+        CurlShareError { code: c, message: share_strerror(c) }
+    }
+}
+
+impl fmt::Show for CurlShareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (curl share code {})", self.message, self.code as int)
+    }
+}
+
+/// Converts a curl::code::CURLSHcode into its error string.
+pub fn share_strerror(c: code::CURLSHcode) -> String {
+    use std::str::raw::from_c_str;
+
+    unsafe {
+        from_c_str(curl_share_strerror(c))
+    }
+}
+
+/// The kinds of data a CurlShare can share between handles
+#[deriving(Show, Clone, PartialEq)]
+pub enum ShareData {
+    /// The cookie engine's store
+    ShareCookies,
+    /// Resolved host names
+    ShareDns,
+    /// TLS session ids, so reconnects can skip the full handshake
+    ShareSslSessions,
+    /// The connection pool, so open connections are reused by every handle
+    ShareConnections
+}
+
+/// Wrapper over the CURLSH share handle. Handles given the same share
+/// (see `Curl::easy_share`) use one DNS cache, cookie store, TLS session
+/// cache and/or connection pool. The shared data is protected by locks,
+/// so the handles may be used from different tasks.
+///
+/// # Example
+/// ~~~ {.rust}
+/// use sync::Arc;
+///
+/// let share = Arc::new(CurlShare::new([ShareDns, ShareSslSessions]).unwrap());
+///
+/// let mut a = Curl::new();
+/// let mut b = Curl::new();
+/// a.easy_share(share.clone());
+/// b.easy_share(share.clone());
+/// ~~~
+pub struct CurlShare {
+    share: *CURLSH,
+    // one lock per curl_lock_data value; the vector's buffer never moves
+    locks: Vec<NativeMutex>
+}
+
+impl CurlShare {
+    /// Return a new share handle sharing the given kinds of data
+    /// # Arguments
+    /// * `data` -  what to share
+    pub fn new(data: &[ShareData]) -> CurlShareResult<CurlShare> {
+        let mut locks = vec![];
+        for _ in range(0, CURL_LOCK_DATA_LAST as uint) {
+            locks.push(unsafe { NativeMutex::new() });
+        }
+
+        let share = CurlShare { share: unsafe { curl_share_init() }, locks: locks };
+
+        unsafe {
+            let user_data = share.locks.as_ptr() as *c_void;
+            try!(check(curl_share_setopt(share.share, CURLSHOPT_LOCKFUNC, mem::transmute(c_lock_fn))));
+            try!(check(curl_share_setopt(share.share, CURLSHOPT_UNLOCKFUNC, mem::transmute(c_unlock_fn))));
+            try!(check(curl_share_setopt(share.share, CURLSHOPT_USERDATA, user_data)));
+
+            for d in data.iter() {
+                let lock_data = match *d {
+                    ShareCookies => CURL_LOCK_DATA_COOKIE,
+                    ShareDns => CURL_LOCK_DATA_DNS,
+                    ShareSslSessions => CURL_LOCK_DATA_SSL_SESSION,
+                    ShareConnections => CURL_LOCK_DATA_CONNECT
+                };
+                try!(check(curl_share_setopt(share.share, CURLSHOPT_SHARE, lock_data as int as *c_void)));
+            }
+        }
+
+        Ok(share)
+    }
+
+    /// The raw share handle, for CURLOPT_SHARE
+    pub fn handle(&self) -> *CURLSH {
+        self.share
+    }
+}
+
+impl Drop for CurlShare {
+    fn drop(&mut self) {
+        unsafe {
+            curl_share_cleanup(self.share);
+        }
+    }
+}
+
+/// Turns a code returned by a share function into a result
+fn check(c: code::CURLSHcode) -> CurlShareResult<()> {
+    match c {
+        code::CURLSHE_OK => Ok(()),
+        _ => Err(CurlShareError::new(c))
+    }
+}
+
+/// Lock callback called by libcurl before it touches shared data
+/// # Safety Notes
+/// user_data is the first element of the CurlShare's locks, indexed by `data`
+extern "C" fn c_lock_fn(_handle: *CURL, data: c_int, _access: c_int, user_data: *c_void) {
+    unsafe {
+        let lock = (user_data as *NativeMutex).offset(data as int);
+        (*lock).lock_noguard();
+    }
+}
+
+/// Unlock callback called by libcurl when it is done with shared data
+extern "C" fn c_unlock_fn(_handle: *CURL, data: c_int, user_data: *c_void) {
+    unsafe {
+        let lock = (user_data as *NativeMutex).offset(data as int);
+        (*lock).unlock_noguard();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sync::Arc;
+    use curl::{Curl,URL,CookieFile};
+    use curl::curl_ll::CURLINFO_COOKIELIST;
+    use curl::handler::CurlHandlers;
+
+    #[test]
+    fn test_new_share() {
+        assert!(CurlShare::new([ShareCookies, ShareDns, ShareSslSessions]).is_ok());
+    }
+
+    #[test]
+    fn test_handles_share_cookies() {
+        let share = Arc::new(CurlShare::new([ShareCookies, ShareDns]).unwrap());
+
+        let mut a = Curl::new();
+        let mut b = Curl::new();
+        a.easy_share(share.clone()).unwrap();
+        b.easy_share(share.clone()).unwrap();
+
+        a.easy_setopt(CookieFile("")).unwrap();
+        b.easy_setopt(CookieFile("")).unwrap();
+
+        a.easy_setopt(URL("http://httpbin.org/cookies/set?shared=yes")).unwrap();
+        a.easy_perform_with(&mut CurlHandlers::new().write(|d| d.len())).unwrap();
+
+        let cookies = b.easy_getinfo(CURLINFO_COOKIELIST).unwrap();
+        assert!(cookies.as_list().unwrap().iter().any(|c| c.as_slice().contains("shared")));
+    }
+}
@@ -11,53 +11,52 @@ pub fn example_http_get() {
 
     match data_res {
         Ok(data) => { println!("{}", from_utf8(data.as_slice())); }
-        Err(msg) => { fail!("Error".to_str() + msg); }
+        Err(err) => { fail!("Error {}", err); }
     };
 }
 
 /// This function is an example of the http_client usage
 pub fn example_http_basic_client() {
     use http_client::HttpClient;
-    use std::collections::hashmap::HashMap;
+    use headers::Headers;
     use std::str::from_utf8;
-    use request::Request;
+    use request::{Request,GET};
 
     let client = HttpClient::new();
 
     let url = "http://api.4chan.org/pol/threads.json";
 
-    let req = Request::new(url.to_string(),HashMap::new(),vec![]);
+    let req = Request::new(GET,url.to_string(),Headers::new(),vec![]);
 
     let resp_res = client.exec(&req);
 
     match resp_res {
         Ok(data) => { println!("{}", from_utf8(data.body.as_slice())); }
-        Err(msg) => { fail!("Error".to_str() + msg); }
+        Err(err) => { fail!("Error {}", err); }
     };
 }
 
 /// A bit more advanced http_client usage
 pub fn example_client_more() {
     use http_client::HttpClient;
-    use std::collections::hashmap::HashMap;
     use std::str::from_utf8;
-    use request::Request;
+    use request::{Request,GET};
     use headers;
 
      let client = HttpClient::new();
 
     let url = "http://api.4chan.org/pol/threads.json";
-    let mut headers = HashMap::new();
-    headers.insert(headers::request::ACCEPT.to_string(),"application/json".to_str());
+    let mut headers = headers::Headers::new();
+    headers.add(headers::request::ACCEPT, "application/json");
 
-    let req = Request::new(url.to_string(),headers,vec![]);
+    let req = Request::new(GET,url.to_string(),headers,vec![]);
 
     let resp_res = client.exec(&req);
 
     match resp_res {
-        Err(msg) => { fail!("Error".to_str() + msg); }
+        Err(err) => { fail!("Error {}", err); }
         Ok(resp) => {
-            for (k, v) in resp.headers.iter() {
+            for &(ref k, ref v) in resp.headers.iter() {
                 println!("{}: {}",*k,*v);
             }
 
@@ -104,22 +103,43 @@ impl CurlCallback<u8, Vec<u8>> for ExampleWriteBuf {
 /// This shows some very basic usage of the curl_easy* interface
 pub fn example_http_easy_basic_functionality() {
     use curl::Curl;
-    use curl::code;
     use curl::opt;
     use std::str::from_utf8;
 
     let curl = Curl::new();
     let buf = ExampleWriteBuf { data: vec![] };
 
-    curl.easy_setopt(curl::URL("www.google.com"));
-    curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &buf);
+    curl.easy_setopt(curl::URL("www.google.com")).unwrap();
+    curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &buf).unwrap();
 
-    let err = curl.easy_perform();
-
-    match err {
-        code::CURLE_OK => {
+    match curl.easy_perform() {
+        Ok(()) => {
             println!("{}", from_utf8(buf.data.as_slice()));
         }
-        _ => { fail!(curl::easy_strerror(err)); }
+        Err(err) => { fail!(curl::easy_strerror(err.code)); }
+    }
+}
+
+/// The same as above, using Rust closures as the callbacks instead of
+/// implementing the CurlCallback trait
+pub fn example_http_easy_handlers() {
+    use curl::Curl;
+    use curl::handler::CurlHandlers;
+    use std::str::from_utf8;
+
+    let curl = Curl::new();
+    let mut body = vec![];
+
+    curl.easy_setopt(curl::URL("www.google.com")).unwrap();
+
+    let res = curl.easy_perform_with(&mut CurlHandlers::new()
+        .write(|data| { body.push_all(data); data.len() })
+        .progress(|dltotal, dlnow, _, _| { println!("{}/{} bytes", dlnow, dltotal); true }));
+
+    match res {
+        Ok(()) => {
+            println!("{}", from_utf8(body.as_slice()));
+        }
+        Err(err) => { fail!(curl::easy_strerror(err.code)); }
     }
 }
@@ -0,0 +1,168 @@
+use std::cell::RefCell;
+use std::fmt;
+
+use curl::Curl;
+use curl::error::{CurlError,CurlResult};
+use curl::mime::CurlMime;
+use curl::code;
+
+/// A multipart/form-data body, as sent by a browser submitting a form
+/// with file inputs. Parts are sent in the order they were added.
+///
+/// # Example
+/// ~~~ {.rust}
+/// let form = MultipartForm::new()
+///     .text("title", "Holiday")
+///     .file("photo", &Path::new("beach.jpg"))
+///     .part(Part::bytes("thumb", thumbnail).filename("thumb.png").content_type("image/png"));
+///
+/// let req = Request::post("http://example.com/albums").form(form);
+/// let resp = client.exec(&req);
+/// ~~~
+pub struct MultipartForm {
+    parts: Vec<Part>
+}
+
+/// One field of a MultipartForm
+pub struct Part {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    data: PartData
+}
+
+enum PartData {
+    Bytes(Vec<u8>),
+    File(Path),
+    // taken on the first send, so a form with one can only be sent once
+    Stream(RefCell<Option<Box<Reader:Send>>>, Option<u64>)
+}
+
+impl MultipartForm {
+    /// Return a form without parts
+    pub fn new() -> MultipartForm {
+        MultipartForm { parts: vec![] }
+    }
+
+    /// Add a plain text field
+    pub fn text(self, name: &str, value: &str) -> MultipartForm {
+        self.part(Part::text(name, value))
+    }
+
+    /// Add a file upload, read from `path` while the request is sent
+    pub fn file(self, name: &str, path: &Path) -> MultipartForm {
+        self.part(Part::file(name, path))
+    }
+
+    /// Add a part
+    pub fn part(mut self, part: Part) -> MultipartForm {
+        self.parts.push(part);
+        self
+    }
+
+    /// Builds the curl_mime structure sending this form on `curl`. Fails
+    /// with CURLE_SEND_FAIL_REWIND if a streamed part was already sent.
+    pub fn to_mime(&self, curl: &Curl) -> CurlResult<CurlMime> {
+        let mut mime = try!(CurlMime::new(curl));
+
+        for p in self.parts.iter() {
+            let mut part = mime.add_part();
+            try!(part.name(p.name.as_slice()));
+
+            match p.data {
+                Bytes(ref data) => try!(part.data(data.as_slice())),
+                File(ref path) => try!(part.file(path)),
+                Stream(ref reader, size) => {
+                    let reader = match reader.borrow_mut().take() {
+                        Some(r) => r,
+                        None => {
+                            let mut err = CurlError::new(code::CURLE_SEND_FAIL_REWIND);
+                            err.message = format!("form part {} was streamed already", p.name);
+                            return Err(err);
+                        }
+                    };
+                    try!(part.reader(reader, size));
+                }
+            }
+
+            // after the data, as file parts default to the file's name
+            match p.filename {
+                Some(ref f) => try!(part.filename(f.as_slice())),
+                None => { ; }
+            }
+            match p.content_type {
+                Some(ref t) => try!(part.mime_type(t.as_slice())),
+                None => { ; }
+            }
+        }
+
+        Ok(mime)
+    }
+}
+
+impl Part {
+    /// A plain text field
+    pub fn text(name: &str, value: &str) -> Part {
+        Part::bytes(name, Vec::from_slice(value.as_bytes()))
+    }
+
+    /// A field holding `data`
+    pub fn bytes(name: &str, data: Vec<u8>) -> Part {
+        Part::with_data(name, Bytes(data))
+    }
+
+    /// A file upload, read from `path` while the request is sent. The
+    /// file name sent is the path's base name.
+    pub fn file(name: &str, path: &Path) -> Part {
+        Part::with_data(name, File(path.clone()))
+    }
+
+    /// A field streamed from `reader` while the request is sent. Without
+    /// a `size` the body is sent with chunked encoding.
+    pub fn reader(name: &str, reader: Box<Reader:Send>, size: Option<u64>) -> Part {
+        Part::with_data(name, Stream(RefCell::new(Some(reader)), size))
+    }
+
+    /// Set the file name sent for the part
+    pub fn filename(mut self, filename: &str) -> Part {
+        self.filename = Some(filename.to_string());
+        self
+    }
+
+    /// Set the part's Content-Type
+    pub fn content_type(mut self, content_type: &str) -> Part {
+        self.content_type = Some(content_type.to_string());
+        self
+    }
+
+    fn with_data(name: &str, data: PartData) -> Part {
+        Part { name: name.to_string(), filename: None, content_type: None, data: data }
+    }
+}
+
+impl fmt::Show for MultipartForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self.parts.iter().map(|p| p.name.as_slice()).collect();
+        write!(f, "MultipartForm {}", names)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use curl::Curl;
+    use curl::code;
+    use std::io::MemReader;
+
+    #[test]
+    fn test_streamed_part_is_sent_once() {
+        let reader = box MemReader::new(vec![1u8, 2, 3]);
+        let form = MultipartForm::new()
+            .text("a", "b")
+            .part(Part::reader("data", reader, Some(3)).content_type("application/octet-stream"));
+
+        let curl = Curl::new();
+        assert!(form.to_mime(&curl).is_ok());
+        assert!(form.to_mime(&curl).unwrap_err().code == code::CURLE_SEND_FAIL_REWIND);
+    }
+}
@@ -1,4 +1,6 @@
-use std::collections::hashmap::HashMap;
+use std::ascii::StrAsciiExt;
+use std::fmt;
+use std::slice::Items;
 
 /// This module contains static strings of the HTTP request headers
 /// found on https://en.wikipedia.org/wiki/List_of_HTTP_header_fields#Requests
@@ -13,6 +15,7 @@ pub mod request {
     pub static CONNECTION: &'static str = "Connection";
     pub static CONTENT_LENGTH: &'static str = "Content-Length";
     pub static CONTENT_MD5: &'static str = "Content-MD5";
+    pub static CONTENT_TYPE: &'static str = "Content-Type";
     pub static COOKIE: &'static str = "Cookie";
     pub static DATE: &'static str = "Date";
     pub static EXPECT: &'static str = "Expect";
@@ -72,5 +75,162 @@ pub mod response {
     pub static WWW_AUTHENTICATE: &'static str = "WWW-Authenticate";
 }
 
-/// This is a simple type alias for a map of headers
-pub type Headers = HashMap<String,String>;
+/// An ordered collection of HTTP headers. Repeated headers are all kept,
+/// in the order they were added, and names are compared case-insensitively.
+#[deriving(Clone, PartialEq)]
+pub struct Headers {
+    entries: Vec<(String,String)>
+}
+
+impl Headers {
+    /// Return a new, empty collection of headers
+    /// # Example
+    /// ~~~ {.rust}
+    /// let mut headers = Headers::new();
+    /// headers.add(headers::request::ACCEPT, "application/json");
+    /// ~~~
+    pub fn new() -> Headers {
+        Headers { entries: vec![] }
+    }
+
+    /// Add a header, keeping any headers already present with the same name
+    /// # Arguments
+    /// * `name` -  the header name
+    /// * `value` - the header value
+    pub fn add(&mut self, name: &str, value: &str) {
+        self.entries.push((name.to_string(), value.to_string()));
+    }
+
+    /// Set a header, replacing all headers already present with the same name
+    /// # Arguments
+    /// * `name` -  the header name
+    /// * `value` - the header value
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.remove(name);
+        self.add(name, value);
+    }
+
+    /// Returns the value of the first header with the given name
+    /// # Example
+    /// ~~~ {.rust}
+    /// let mut headers = Headers::new();
+    /// headers.add("Content-Type", "text/html");
+    /// assert_eq!(headers.get("content-type"), Some("text/html"));
+    /// ~~~
+    pub fn get<'a>(&'a self, name: &str) -> Option<&'a str> {
+        for &(ref k, ref v) in self.entries.iter() {
+            if k.as_slice().eq_ignore_ascii_case(name) {
+                return Some(v.as_slice());
+            }
+        }
+        None
+    }
+
+    /// Returns the values of every header with the given name, in order
+    /// # Example
+    /// ~~~ {.rust}
+    /// for link in resp.headers.get_all(headers::response::LINK).iter() {
+    ///     println!("{}", *link);
+    /// }
+    /// ~~~
+    pub fn get_all<'a>(&'a self, name: &str) -> Vec<&'a str> {
+        self.entries.iter()
+            .filter(|&&(ref k, _)| k.as_slice().eq_ignore_ascii_case(name))
+            .map(|&(_, ref v)| v.as_slice())
+            .collect()
+    }
+
+    /// True if at least one header with the given name is present
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Remove every header with the given name, returning true if any were present
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|&(ref k, _)| !k.as_slice().eq_ignore_ascii_case(name));
+        self.entries.len() != before
+    }
+
+    /// Remove all headers
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The number of headers, counting repeated headers separately
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    /// True if there are no headers
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over all (name, value) pairs in the order they were added
+    /// # Example
+    /// ~~~ {.rust}
+    /// for &(ref name, ref value) in resp.headers.iter() {
+    ///     println!("{}: {}", *name, *value);
+    /// }
+    /// ~~~
+    pub fn iter<'a>(&'a self) -> Items<'a, (String,String)> {
+        self.entries.iter()
+    }
+}
+
+impl fmt::Show for Headers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}", "{"));
+        for (i, &(ref k, ref v)) in self.entries.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}: {}", *k, *v));
+        }
+        write!(f, "{}", "}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_case_insensitive_lookup() {
+        let mut headers = Headers::new();
+        headers.add("Content-Type", "text/html");
+
+        assert_eq!(headers.get("content-type"), Some("text/html"));
+        assert_eq!(headers.get("CONTENT-TYPE"), Some("text/html"));
+        assert!(headers.contains("Content-type"));
+        assert_eq!(headers.get("Content-Length"), None);
+    }
+
+    #[test]
+    fn test_repeated_headers_keep_order() {
+        let mut headers = Headers::new();
+        headers.add("Vary", "Accept");
+        headers.add("Link", "</a>; rel=next");
+        headers.add("vary", "Accept-Encoding");
+
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get("Vary"), Some("Accept"));
+        assert_eq!(headers.get_all("Vary"), vec!["Accept", "Accept-Encoding"]);
+
+        let names: Vec<&str> = headers.iter().map(|&(ref k, _)| k.as_slice()).collect();
+        assert_eq!(names, vec!["Vary", "Link", "vary"]);
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut headers = Headers::new();
+        headers.add("Accept", "text/html");
+        headers.add("accept", "text/plain");
+        headers.set("ACCEPT", "application/json");
+
+        assert_eq!(headers.get_all("Accept"), vec!["application/json"]);
+
+        assert!(headers.remove("accept"));
+        assert!(!headers.remove("accept"));
+        assert!(headers.is_empty());
+    }
+}
@@ -1,39 +1,237 @@
-use std::collections::hashmap::HashMap;
-use libc::{size_t,c_char};
-use std::mem;
-
 use curl::*;
-use curl::callback::*;
+use curl::code;
+use curl::error::{CurlError,CurlResult};
+use curl::handler::CurlHandlers;
+use curl::share::{CurlShare,ShareCookies,ShareDns,ShareSslSessions};
 use request::*;
-use response::Response;
-use curl::curl_ll::{curl_slist,curl_slist_append,curl_slist_free_all};
+use response::{Response,TransferStats,Redirect};
+use tls::{TlsConfig,Certificate};
+use proxy::ProxyConfig;
+use headers;
+use headers::Headers;
+use curl::curl_ll::{CURLINFO,CURLINFO_RESPONSE_CODE,CURLINFO_COOKIELIST,CURLINFO_EFFECTIVE_URL,CURLINFO_NAMELOOKUP_TIME,
+                    CURLINFO_CONNECT_TIME,CURLINFO_APPCONNECT_TIME,CURLINFO_PRETRANSFER_TIME,
+                    CURLINFO_STARTTRANSFER_TIME,CURLINFO_TOTAL_TIME,CURLINFO_REDIRECT_TIME,
+                    CURLINFO_REDIRECT_COUNT,CURLINFO_SIZE_DOWNLOAD,CURLINFO_SIZE_UPLOAD,
+                    CURLINFO_SPEED_DOWNLOAD,CURLINFO_SPEED_UPLOAD,CURLINFO_NUM_CONNECTS,CURLINFO_CERTINFO,
+                    CURL_REDIR_POST_ALL};
+use cookie::Cookie;
+use sync::{Arc,Mutex};
+use std::ascii::StrAsciiExt;
 
+/// The status line and headers of a response, built up line by line
 struct HttpHeaders {
-    map: HashMap<String,String>
+    map: Headers,
+    status_code: uint,
+    http_version: String,
+    reason: String
 }
 
 impl HttpHeaders {
     fn new() -> HttpHeaders {
-        HttpHeaders { map: HashMap::new() }
+        HttpHeaders { map: Headers::new(), status_code: 0, http_version: String::new(), reason: String::new() }
     }
-}
 
-impl CurlCallback<c_char, HashMap<String,String>> for HttpHeaders {
-    fn curl_get_userdata<'a>(&'a self) -> &'a HashMap<String,String> {
-        &'a self.map
-    }
+    /// Takes in a header line as received by the header handler. Returns
+    /// true if the line was a status line.
+    fn add_line(&mut self, line: &str) -> bool {
+        // A status line starts a new response (after a redirect or a 100 Continue),
+        // so only the headers of the last response are kept
+        match parse_status_line(line) {
+            Some((version, code, reason)) => {
+                self.status_code = code;
+                self.http_version = version;
+                self.reason = reason;
+                self.map.clear();
+                return true;
+            }
+            None => { ; }
+        }
 
-    fn curl_get_callback(&self) -> CurlCallbackType<c_char, HashMap<String,String>> {
-        unsafe {
-            mem::transmute(c_curl_http_header_fn)
+        match line.find(':') {
+            Some(colon) => { self.map.add(line.slice_to(colon).trim(), line.slice_from(colon + 1).trim()); }
+            None => { ; }
         }
+        false
     }
 }
 
 /// Rather opaque struct serving as HttpClient
+///
+/// A client keeps a pool of easy handles and checks one out for every
+/// request, so it can be used from several tasks at once. Clones share the
+/// pool, along with the cookies, DNS cache and TLS sessions of every handle
+/// in it. Connections are not shared: each handle keeps its own, since
+/// handles busy in different tasks must not use one connection cache.
 #[deriving(Clone)]
 pub struct HttpClient {
-    curl: Curl
+    pool: Arc<Mutex<Vec<Curl>>>,
+    // None if libcurl could not make a share handle
+    share: Option<Arc<CurlShare>>,
+    base_url: Option<String>,
+    headers: Headers,
+    settings: Settings,
+    // None when cookies are off, "" for an in-memory jar, else the jar file;
+    // shared like the pool, so every clone sees cookies turned on
+    cookie_jar: Arc<Mutex<Option<String>>>
+}
+
+/// An easy handle checked out of a client's pool. It is reset and put back
+/// when dropped; resetting keeps its connections open for the next request.
+struct PooledCurl<'a> {
+    pool: &'a Mutex<Vec<Curl>>,
+    curl: Option<Curl>
+}
+
+impl<'a> Deref<Curl> for PooledCurl<'a> {
+    fn deref<'b>(&'b self) -> &'b Curl {
+        self.curl.get_ref()
+    }
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for PooledCurl<'a> {
+    fn drop(&mut self) {
+        let curl = self.curl.take_unwrap();
+        curl.easy_reset();
+        self.pool.lock().push(curl);
+    }
+}
+
+/// Builds an HttpClient with settings applied to every request it makes
+/// # Example
+/// ~~~ {.rust}
+/// let client = HttpClientBuilder::new()
+///     .base_url("https://api.example.com/v1")
+///     .user_agent("my-app/1.0")
+///     .header(headers::request::ACCEPT, "application/json")
+///     .timeout(10000)
+///     .build();
+///
+/// // fetches https://api.example.com/v1/users
+/// let req = Request::new(GET,"users".to_string(),Headers::new(),vec![]);
+/// let resp = client.exec(&req);
+/// ~~~
+pub struct HttpClientBuilder {
+    base_url: Option<String>,
+    headers: Headers,
+    settings: Settings
+}
+
+impl HttpClientBuilder {
+    /// Return a builder with nothing set
+    pub fn new() -> HttpClientBuilder {
+        HttpClientBuilder { base_url: None, headers: Headers::new(), settings: Settings::new() }
+    }
+
+    /// URL that relative request URLs are resolved against
+    pub fn base_url(mut self, url: &str) -> HttpClientBuilder {
+        self.base_url = Some(url.to_string());
+        self
+    }
+
+    /// Send this header with every request that does not set it itself
+    pub fn header(mut self, name: &str, value: &str) -> HttpClientBuilder {
+        self.headers.add(name, value);
+        self
+    }
+
+    /// Default User-Agent header
+    pub fn user_agent(self, agent: &str) -> HttpClientBuilder {
+        self.header(headers::request::USER_AGENT, agent)
+    }
+
+    /// Limit on each whole transfer, in milliseconds
+    pub fn timeout(mut self, ms: uint) -> HttpClientBuilder {
+        self.settings.timeout_ms = Some(ms);
+        self
+    }
+
+    /// Limit on connecting, in milliseconds
+    pub fn connect_timeout(mut self, ms: uint) -> HttpClientBuilder {
+        self.settings.connect_timeout_ms = Some(ms);
+        self
+    }
+
+    /// Send every request through the proxy at this URL
+    pub fn proxy(self, url: &str) -> HttpClientBuilder {
+        self.proxy_config(ProxyConfig::new(url))
+    }
+
+    /// Send every request through this proxy
+    pub fn proxy_config(mut self, proxy: ProxyConfig) -> HttpClientBuilder {
+        self.settings.proxy = Some(proxy);
+        self
+    }
+
+    /// Without a proxy set: true to take it from the http_proxy, https_proxy,
+    /// all_proxy and no_proxy environment variables for each request, false
+    /// to ignore them and connect directly
+    pub fn proxy_from_env(mut self, from_env: bool) -> HttpClientBuilder {
+        self.settings.proxy_from_env = Some(from_env);
+        self
+    }
+
+    /// Verify servers and present client certificates as `tls` says.
+    /// Anything `tls` leaves unset keeps its earlier value.
+    pub fn tls(mut self, tls: TlsConfig) -> HttpClientBuilder {
+        let merged = match self.settings.tls {
+            Some(ref existing) => tls.or(existing),
+            None => tls
+        };
+        self.settings.tls = Some(merged);
+        self
+    }
+
+    /// Whether to check the server's certificate and host name
+    pub fn verify_tls(self, verify: bool) -> HttpClientBuilder {
+        self.tls(TlsConfig::new().verify(verify))
+    }
+
+    /// Verify servers against the CA certificates in `path`
+    pub fn ca_bundle(self, path: &Path) -> HttpClientBuilder {
+        self.tls(TlsConfig::new().ca_bundle(path))
+    }
+
+    /// Follow at most `max` redirects, none if 0
+    pub fn max_redirects(mut self, max: uint) -> HttpClientBuilder {
+        self.settings.redirects = Some(RedirectPolicy::limit(max));
+        self
+    }
+
+    /// Follow redirects as `policy` says
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> HttpClientBuilder {
+        self.settings.redirects = Some(policy);
+        self
+    }
+
+    /// Whether to ask for compressed bodies and decode them, true by default
+    pub fn decode_content(mut self, decode: bool) -> HttpClientBuilder {
+        self.settings.decode_content = Some(decode);
+        self
+    }
+
+    /// Authenticate to servers with this user name and password
+    pub fn auth(mut self, user: &str, password: &str) -> HttpClientBuilder {
+        self.settings.auth = Some((user.to_string(), password.to_string()));
+        self
+    }
+
+    /// Return the configured client
+    pub fn build(self) -> HttpClient {
+        // without a share each pooled handle keeps its own caches, and the
+        // cookie methods fail rather than reach only one handle
+        let share = CurlShare::new([ShareCookies, ShareDns, ShareSslSessions]).ok();
+
+        HttpClient {
+            pool: Arc::new(Mutex::new(vec![])),
+            share: share.map(|s| Arc::new(s)),
+            base_url: self.base_url,
+            headers: self.headers,
+            settings: self.settings,
+            cookie_jar: Arc::new(Mutex::new(None))
+        }
+    }
 }
 
 impl HttpClient {
@@ -41,11 +239,75 @@ impl HttpClient {
     /// # Example
     /// ~~~ {.rust}
     /// let client = HttpClient::new();
+    ///
+    /// // clones share handles, so this can be sent to another task
+    /// let other = client.clone();
     /// ~~~
     pub fn new() -> HttpClient {
-        let cl = HttpClient { curl: Curl::new() };
-        cl.curl.easy_setopt(FollowLocation(true));
-        cl
+        HttpClientBuilder::new().build()
+    }
+
+    /// Turn on the cookie engine, so cookies set by responses are sent
+    /// back on later requests made with this client. The cookies are
+    /// only kept in memory.
+    ///
+    /// This and the other cookie methods fail with CURLE_NOT_BUILT_IN if
+    /// libcurl could not make the share handle the pooled handles keep
+    /// their cookies in.
+    pub fn enable_cookies(&self) -> CurlResult<()> {
+        try!(self.check_cookie_share());
+
+        let mut jar = self.cookie_jar.lock();
+        if jar.is_none() {
+            *jar = Some(String::new());
+        }
+        Ok(())
+    }
+
+    /// Turn on the cookie engine with a persistent jar. Cookies are loaded
+    /// from `path` now and written back to it after every request.
+    /// # Arguments
+    /// * `path` -  the cookie file, in Netscape format; need not exist yet
+    /// # Example
+    /// ~~~ {.rust}
+    /// let client = HttpClient::new();
+    /// client.set_cookie_jar(&Path::new("cookies.txt"));
+    /// ~~~
+    pub fn set_cookie_jar(&self, path: &Path) -> CurlResult<()> {
+        let file = format!("{}", path.display());
+
+        {
+            let curl = try!(self.cookie_engine());
+            try!(curl.easy_setopt(CookieFile(file.as_slice())));
+            try!(curl.easy_setopt(CookieList("RELOAD")));
+        }
+        *self.cookie_jar.lock() = Some(file);
+        Ok(())
+    }
+
+    /// All cookies currently held by the cookie engine
+    pub fn cookies(&self) -> CurlResult<Vec<Cookie>> {
+        let curl = try!(self.cookie_engine());
+        let lines = try!(curl.easy_getinfo(CURLINFO_COOKIELIST));
+
+        Ok(match lines {
+            InfoList(l) => l.iter().filter_map(|line| Cookie::parse_netscape(line.as_slice())).collect(),
+            _ => vec![]
+        })
+    }
+
+    /// Add a cookie to the cookie engine, as if a response had set it
+    /// # Arguments
+    /// * `set_cookie` -    a Set-Cookie header line, e.g. "Set-Cookie: sid=abc; Domain=example.com"
+    pub fn add_cookie(&self, set_cookie: &str) -> CurlResult<()> {
+        let curl = try!(self.cookie_engine());
+        curl.easy_setopt(CookieList(set_cookie))
+    }
+
+    /// Forget every cookie held by the cookie engine
+    pub fn clear_cookies(&self) -> CurlResult<()> {
+        let curl = try!(self.cookie_engine());
+        curl.easy_setopt(CookieList("ALL"))
     }
 
     /// Execute the given request
@@ -58,98 +320,490 @@ impl HttpClient {
     /// let client = HttpClient::new();
     ///
     /// let url = "http://api.4chan.org/pol/threads.json";
-    /// let mut headers = HashMap::new();
-    /// headers.insert(headers::request::ACCEPT.to_string(),~"application/json");
+    /// let mut headers = Headers::new();
+    /// headers.add(headers::request::ACCEPT, "application/json");
     ///
-    /// let req = Request::new(url.to_string(),headers,~[]);
+    /// let req = Request::new(GET,url.to_string(),headers,~[]);
     ///
     /// let resp_res = client.exec(&req);
     ///
     /// match resp_res {
     ///     Ok(_) => { ; }
-    ///     Err(msg) => { fail!("Error" + msg); }
+    ///     Err(err) => { fail!("Error {}", err); }
     /// };
     /// ~~~
-    pub fn exec(&self, req: &Request) -> Result<Response,String> {
-        let url = req.url.to_str();
-        let body = SimpleCurlByteBuffer::new();
-        let headers = HttpHeaders::new();
-
-        self.curl.easy_setopt(URL(url.as_slice()));
-        self.curl.easy_setopt_callback(opt::WRITEDATA, opt::WRITEFUNCTION, &body);
-        self.curl.easy_setopt_callback(opt::HEADERDATA, opt::HEADERFUNCTION, &headers);
-
-        // FIXME setting headers like this is somewhat nasty - fix this with chaining or something
-        let mut list = 0 as *curl_slist;
-        if !req.headers.is_empty() {
-            unsafe {
-                for (k, v) in req.headers.iter() {
-                    let h = format!("{}: {}",*k,*v);
-
-                    h.with_c_str(|s| {
-                        list = curl_slist_append(list,s);
-                    });
-                }
-                self.curl.easy_setopt(UnsafeStringList(opt::HTTPHEADER, list));
+    pub fn exec(&self, req: &Request) -> CurlResult<Response> {
+        let mut body = vec![];
+        let mut resp = try!(self.exec_with(req, |data| { body.push_all(data); true }));
+
+        resp.body = body;
+        Ok(resp)
+    }
+
+    /// Execute the given request, streaming the response body into `writer`
+    /// as it arrives instead of holding it in memory. The body of the
+    /// returned response is empty.
+    /// # Arguments
+    /// * `req` -   request to be executed
+    /// * `writer` -    where the response body is written
+    /// # Example
+    /// ~~~ {.rust}
+    /// use std::io::File;
+    ///
+    /// let client = HttpClient::new();
+    /// let req = Request::new(GET,"http://example.com/big.iso".to_string(),Headers::new(),vec![]);
+    ///
+    /// let mut file = File::create(&Path::new("big.iso"));
+    /// let resp = client.exec_to(&req, &mut file);
+    /// ~~~
+    pub fn exec_to(&self, req: &Request, writer: &mut Writer) -> CurlResult<Response> {
+        let mut io_err = None;
+
+        let res = self.exec_with(req, |data| {
+            match writer.write(data) {
+                Ok(()) => true,
+                Err(e) => { io_err = Some(e); false }
+            }
+        });
+
+        match (res, io_err) {
+            // curl only knows the write failed, the writer knows why
+            (Err(mut err), Some(e)) => {
+                err.message = format!("failed writing body: {}", e);
+                Err(err)
             }
+            (res, _) => res
         }
+    }
+
+    /// Execute the given request, handing each chunk of the response body to
+    /// `on_data`, which returns false to abort the transfer
+    fn exec_with(&self, req: &Request, on_data: |&[u8]| -> bool) -> CurlResult<Response> {
+        use std::str::from_utf8_lossy;
+
+        let curl = try!(self.checkout());
+        let settings = req.settings.or(&self.settings);
+        let mut head = HttpHeaders::new();
+        // every response seen, as (url, status), redirects and 100 Continues included
+        let mut hops = vec![];
+
+        let defaults = self.headers.iter().filter(|&&(ref k, _)| !req.headers.contains(k.as_slice()));
+        let lines: Vec<String> = req.headers.iter().chain(defaults)
+            .map(|&(ref k, ref v)| format!("{}: {}", *k, *v))
+            .collect();
 
         // Do the request
-        let err = self.curl.easy_perform();
-        
-        if list as uint != 0 {
-            unsafe {
-                curl_slist_free_all(list);
+        let res = {
+            let mut handlers = CurlHandlers::new()
+                .write(|data| match on_data(data) {
+                    true => data.len(),
+                    false => 0
+                })
+                .header(|line| {
+                    if head.add_line(from_utf8_lossy(line).as_slice()) {
+                        hops.push((effective_url(&*curl), head.status_code));
+                    }
+                    line.len()
+                });
+
+            self.perform(&*curl, req, &settings, lines.as_slice(), &mut handlers)
+        };
+
+        let ret = match res {
+            Ok(()) => {
+                let status_code = match curl.easy_getinfo(CURLINFO_RESPONSE_CODE) {
+                    Ok(InfoLong(c)) => c as uint,
+                    _ => 0
+                };
+
+                let mut resp = Response::new(status_code, head.reason, head.http_version,
+                                             head.map, vec![]);
+                resp.url = effective_url(&*curl);
+                resp.redirects = redirects(hops.as_slice());
+                resp.stats = transfer_stats(&*curl);
+                resp.certificates = certificates(&*curl);
+                if settings.decode_content.unwrap_or(true) && req.method != HEAD {
+                    resp.content_encoding = strip_content_encoding(&mut resp.headers);
+                }
+                Ok(resp)
             }
+            Err(err) => Err(err)
+        };
+
+        // persist the cookie jar before the reset forgets where it lives
+        let flushed = match self.cookie_jar() {
+            Some(ref file) if !file.is_empty() => curl.easy_setopt(CookieList("FLUSH")),
+            _ => Ok(())
+        };
+
+        match (ret, flushed) {
+            // a failed transfer is reported before a failed flush
+            (Ok(_), Err(err)) => Err(err),
+            (ret, _) => ret
         }
+    }
+
+    /// Sets up the handle for the request and performs it
+    fn perform(&self, curl: &Curl, req: &Request, settings: &Settings, headers: &[String],
+               handlers: &mut CurlHandlers) -> CurlResult<()> {
+        let url = resolve_url(self.base_url.as_ref().map(|u| u.as_slice()), req.url.as_slice());
+        try!(curl.easy_setopt(URL(url.as_slice())));
+        try!(set_method(curl, req));
+        try!(set_settings(curl, settings));
+        try!(set_proxy(curl, settings, url.as_slice()));
 
-        if err != code::CURLE_OK {
-            return Err(easy_strerror(err));
+        match self.cookie_jar() {
+            Some(ref file) => {
+                // the jar's contents were already loaded, "" keeps the engine on
+                try!(curl.easy_setopt(CookieFile("")));
+                if !file.is_empty() {
+                    try!(curl.easy_setopt(CookieJar(file.as_slice())));
+                }
+            }
+            None => { ; }
         }
 
-        let resp = Response::new(headers.map,body.data);
+        if !headers.is_empty() {
+            // the handle keeps the list until it is reset on its way back to the pool
+            let headers: Vec<&str> = headers.iter().map(|h| h.as_slice()).collect();
+            try!(curl.easy_setopt(HttpHeader(headers.as_slice())));
+        }
 
-        // make sure to reset options for next request
-        self.curl.easy_reset();
+        curl.easy_perform_with(handlers)
+    }
 
-        Ok(resp)
+    /// The current cookie jar setting, see the field
+    fn cookie_jar(&self) -> Option<String> {
+        self.cookie_jar.lock().clone()
+    }
+
+    /// Takes an idle handle from the pool, or makes a new one if every
+    /// handle is busy
+    fn checkout<'a>(&'a self) -> CurlResult<PooledCurl<'a>> {
+        let idle = self.pool.lock().pop();
+
+        let curl = match idle {
+            Some(c) => c,
+            None => {
+                let mut c = Curl::new();
+                match self.share {
+                    Some(ref share) => try!(c.easy_share(share.clone())),
+                    None => { ; }
+                }
+                c
+            }
+        };
+
+        Ok(PooledCurl { pool: &*self.pool, curl: Some(curl) })
+    }
+
+    /// Fails unless the pooled handles keep their cookies in the share
+    /// handle; otherwise a cookie set through one handle would never reach
+    /// requests made on the others
+    fn check_cookie_share(&self) -> CurlResult<()> {
+        match self.share {
+            Some(_) => Ok(()),
+            None => {
+                let mut err = CurlError::new(code::CURLE_NOT_BUILT_IN);
+                err.message = "cookies need a share handle, which libcurl could not make".to_string();
+                Err(err)
+            }
+        }
     }
+
+    /// Checks out a handle to reach the shared cookies through
+    fn cookie_engine<'a>(&'a self) -> CurlResult<PooledCurl<'a>> {
+        try!(self.check_cookie_share());
+        self.checkout()
+    }
+}
+
+/// Configures the handle for the request method. Every method except GET
+/// and HEAD sends the request body, if it has one, with its exact length,
+/// or the form in its place. A form on a GET or HEAD, or next to a body, is refused.
+/// libcurl keeps its own copy of the body, so it can be sent again after
+/// a redirect.
+fn set_method(curl: &Curl, req: &Request) -> CurlResult<()> {
+    match req.form {
+        Some(ref form) => {
+            if req.method == GET || req.method == HEAD || !req.body.is_empty() {
+                let mut err = CurlError::new(code::CURLE_BAD_FUNCTION_ARGUMENT);
+                err.message = format!("a {} request can't send a form{}", req.method.as_str(),
+                                      if req.body.is_empty() { "" } else { " and a body" });
+                return Err(err);
+            }
+
+            let mime = try!(form.to_mime(curl));
+            try!(curl.easy_setopt_mime(mime));
+            return match req.method {
+                POST => Ok(()),
+                ref other => curl.easy_setopt(CustomRequest(other.as_str()))
+            };
+        }
+        None => { ; }
+    }
+
+    match req.method {
+        GET => curl.easy_setopt(HttpGet(true)),
+        HEAD => curl.easy_setopt(NoBody(true)),
+        POST => curl.easy_setopt(PostFields(req.body.as_slice())),
+        ref other if req.body.is_empty() => {
+            // POST mode would add a Content-Type for the missing body
+            curl.easy_setopt(CustomRequest(other.as_str()))
+        }
+        ref other => {
+            try!(curl.easy_setopt(PostFields(req.body.as_slice())));
+            curl.easy_setopt(CustomRequest(other.as_str()))
+        }
+    }
+}
+
+/// Applies the settings of a request to the handle
+fn set_settings(curl: &Curl, settings: &Settings) -> CurlResult<()> {
+    let redirects = settings.redirects.clone().unwrap_or(RedirectPolicy::new());
+    if redirects.max == 0 {
+        try!(curl.easy_setopt(FollowLocation(false)));
+    } else {
+        try!(curl.easy_setopt(FollowLocation(true)));
+        try!(curl.easy_setopt(MaxRedirects(redirects.max as int)));
+        try!(curl.easy_setopt(PostRedir(if redirects.keep_post { CURL_REDIR_POST_ALL } else { 0 })));
+        try!(curl.easy_setopt(RedirProtocols(redirects.protocols)));
+        try!(curl.easy_setopt(UnrestrictedAuth(redirects.send_auth_to_other_hosts)));
+    }
+
+    if settings.decode_content.unwrap_or(true) {
+        // "" offers every encoding this libcurl can decode
+        try!(curl.easy_setopt(AcceptEncoding("")));
+        try!(curl.easy_setopt(HttpContentDecoding(true)));
+    } else {
+        try!(curl.easy_setopt(HttpContentDecoding(false)));
+    }
+
+    match settings.timeout_ms {
+        Some(ms) => try!(curl.easy_setopt(TimeoutMs(ms as int))),
+        None => { ; }
+    }
+    match settings.connect_timeout_ms {
+        Some(ms) => try!(curl.easy_setopt(ConnectTimeoutMs(ms as int))),
+        None => { ; }
+    }
+    match settings.tls {
+        Some(ref tls) => try!(set_tls(curl, tls)),
+        None => { ; }
+    }
+    match settings.auth {
+        Some((ref user, ref pass)) => {
+            try!(curl.easy_setopt(Username(user.as_slice())));
+            try!(curl.easy_setopt(Password(pass.as_slice())));
+        }
+        None => { ; }
+    }
+
+    Ok(())
 }
 
-/// Callback called by libcurl when it receives another header
-/// # Arguments
-/// * `data` - the data received from this call
-/// * `size` - the size each chunk received
-/// * `nmemb` - the number of chunks
-/// * `user_data` - pointer to user_data set when you set up a CurlCallback
-/// # Safety Notes
-/// the size of the header data received is (size * nmemb), and in this case
-/// you should set user_data to be a reference to a `HashMap<~str,~str>`
-/// although you can write such a function yourself that has different user data
-extern "C" fn c_curl_http_header_fn (data: *c_char, size: size_t, nmemb: size_t, user_data: *()) -> size_t {
-    use std::str::raw::from_buf_len;
-    use std::str::*;
+/// Points the handle at the proxy for a request to `url`
+fn set_proxy(curl: &Curl, settings: &Settings, url: &str) -> CurlResult<()> {
+    let proxy = match (&settings.proxy, settings.proxy_from_env) {
+        (&Some(ref proxy), _) => proxy.clone(),
+        (&None, Some(true)) => ProxyConfig::from_env(url).unwrap_or(ProxyConfig::direct()),
+        // an empty proxy also stops libcurl reading the environment
+        (&None, Some(false)) => ProxyConfig::direct(),
+        (&None, None) => { return Ok(()); }
+    };
+
+    let (user, password) = match proxy.credentials {
+        Some((ref u, ref p)) => (Some(u.as_slice()), Some(p.as_slice())),
+        None => (None, None)
+    };
+    try!(curl.easy_setopt(Proxy(proxy.url.as_slice(), user, password)));
+
+    match proxy.kind {
+        Some(ref kind) => try!(curl.easy_setopt(ProxyType(kind.to_curl()))),
+        None => { ; }
+    }
+    match proxy.auth {
+        Some(ref scheme) => try!(curl.easy_setopt(ProxyAuth(scheme.to_curl()))),
+        None => { ; }
+    }
+    match proxy.tunnel {
+        Some(tunnel) => try!(curl.easy_setopt(HttpProxyTunnel(tunnel))),
+        None => { ; }
+    }
+    match proxy.no_proxy {
+        Some(ref hosts) => try!(curl.easy_setopt(NoProxy(hosts.as_slice()))),
+        None => { ; }
+    }
+
+    Ok(())
+}
+
+/// Applies a TLS config to the handle, leaving libcurl's defaults for
+/// anything unset
+fn set_tls(curl: &Curl, tls: &TlsConfig) -> CurlResult<()> {
+    match tls.verify_peer {
+        Some(verify) => try!(curl.easy_setopt(SslVerifyPeer(verify))),
+        None => { ; }
+    }
+    match tls.verify_host {
+        Some(verify) => try!(curl.easy_setopt(SslVerifyHost(verify))),
+        None => { ; }
+    }
+    match tls.cert_chain {
+        // a reused connection has no handshake to take the chain from, so
+        // only requests collecting it pay for a new one
+        Some(true) => {
+            try!(curl.easy_setopt(CertInfo(true)));
+            try!(curl.easy_setopt(FreshConnect(true)));
+        }
+        Some(false) => try!(curl.easy_setopt(CertInfo(false))),
+        None => { ; }
+    }
+    match tls.min_version {
+        Some(ref version) => try!(curl.easy_setopt(SslVersion(version.to_curl()))),
+        None => { ; }
+    }
+
+    let strings = [(&tls.ca_bundle, CaInfo), (&tls.ca_path, CaPath), (&tls.client_cert, SslCert),
+                 (&tls.client_cert_type, SslCertType), (&tls.client_key, SslKey),
+                 (&tls.key_password, KeyPasswd), (&tls.ciphers, SslCipherList),
+                 (&tls.crl_file, CrlFile), (&tls.issuer_cert, IssuerCert),
+                 (&tls.pinned_public_key, PinnedPublicKey)];
+    for &(value, option) in strings.iter() {
+        match *value {
+            Some(ref v) => try!(curl.easy_setopt(option(v.as_slice()))),
+            None => { ; }
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the Content-Encoding of a body curl decoded, and the Content-Length
+/// that went with it. Returns the encoding, if there was one.
+fn strip_content_encoding(headers: &mut Headers) -> Option<String> {
+    let encoding = match headers.get(headers::response::CONTENT_ENCODING) {
+        Some(e) if !e.trim().eq_ignore_ascii_case("identity") => e.trim().to_string(),
+        _ => { return None; }
+    };
+
+    headers.remove(headers::response::CONTENT_ENCODING);
+    headers.remove(headers::response::CONTENT_LENGTH);
+    Some(encoding)
+}
+
+/// The URL the handle is fetching, or last fetched
+fn effective_url(curl: &Curl) -> String {
+    match curl.easy_getinfo(CURLINFO_EFFECTIVE_URL) {
+        Ok(InfoString(url)) => url,
+        _ => String::new()
+    }
+}
+
+/// Picks the redirects out of the responses seen during a transfer. The
+/// last one is the final response, even if it is a redirect that was not
+/// followed.
+fn redirects(hops: &[(String, uint)]) -> Vec<Redirect> {
+    if hops.is_empty() { return vec![]; }
+
+    hops.slice_to(hops.len() - 1).iter()
+        .filter(|&&(_, code)| code >= 300 && code < 400)
+        .map(|&(ref url, code)| Redirect { url: url.clone(), status_code: code })
+        .collect()
+}
+
+/// Reads the server's certificate chain off the handle, empty unless it
+/// was asked for and the transfer was over TLS
+fn certificates(curl: &Curl) -> Vec<Certificate> {
+    match curl.easy_getinfo(CURLINFO_CERTINFO) {
+        Ok(InfoCertChain(chain)) => chain.iter().map(|lines| Certificate::from_lines(lines.as_slice())).collect(),
+        _ => vec![]
+    }
+}
+
+/// Reads the timings and sizes of the last transfer off the handle.
+/// Anything curl can't report is left at 0.
+fn transfer_stats(curl: &Curl) -> TransferStats {
+    let double = |info: CURLINFO| match curl.easy_getinfo(info) {
+        Ok(InfoDouble(d)) => d,
+        _ => 0.0
+    };
+    let long = |info: CURLINFO| match curl.easy_getinfo(info) {
+        Ok(InfoLong(l)) if l > 0 => l as uint,
+        _ => 0
+    };
+
+    TransferStats {
+        namelookup_time: double(CURLINFO_NAMELOOKUP_TIME),
+        connect_time: double(CURLINFO_CONNECT_TIME),
+        appconnect_time: double(CURLINFO_APPCONNECT_TIME),
+        pretransfer_time: double(CURLINFO_PRETRANSFER_TIME),
+        starttransfer_time: double(CURLINFO_STARTTRANSFER_TIME),
+        total_time: double(CURLINFO_TOTAL_TIME),
+        redirect_time: double(CURLINFO_REDIRECT_TIME),
+        redirect_count: long(CURLINFO_REDIRECT_COUNT),
+        size_download: double(CURLINFO_SIZE_DOWNLOAD),
+        size_upload: double(CURLINFO_SIZE_UPLOAD),
+        speed_download: double(CURLINFO_SPEED_DOWNLOAD),
+        speed_upload: double(CURLINFO_SPEED_UPLOAD),
+        num_connects: long(CURLINFO_NUM_CONNECTS)
+    }
+}
+
+/// Resolves a request URL against the client's base URL. Absolute URLs
+/// are used as they are, relative ones are appended to the base.
+fn resolve_url(base: Option<&str>, url: &str) -> String {
+    match base {
+        Some(base) if !has_scheme(url) => {
+            format!("{}/{}", base.trim_right_chars('/'), url.trim_left_chars('/'))
+        }
+        _ => url.to_string()
+    }
+}
+
+/// Whether `url` starts with a scheme and "://": ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )
+fn has_scheme(url: &str) -> bool {
+    let end = match url.find_str("://") {
+        Some(end) if end > 0 => end,
+        _ => { return false; }
+    };
+
+    let scheme = url.slice_to(end);
+    scheme.char_at(0).is_alphabetic() && scheme.chars().all(|c| {
+        (c as u32) < 0x80 && (c.is_alphanumeric() || c == '+' || c == '-' || c == '.')
+    })
+}
 
-    let head = unsafe { from_buf_len(data as *u8,(size * nmemb) as uint) };
+/// Splits an HTTP status line such as `HTTP/1.1 404 Not Found` into
+/// its version, status code and reason phrase
+fn parse_status_line(line: &str) -> Option<(String, uint, String)> {
+    let line = line.trim();
+    if !line.starts_with("HTTP/") { return None; }
 
-    let colon = match head.as_slice().find(':') {
+    let space = match line.find(' ') {
         Some(t) => t,
-        None => { return size * nmemb; },
+        None => { return None; }
     };
 
-    let (name, value) = (head.as_slice().slice(0,colon), head.as_slice().slice(colon + 2 ,head.len() - 1) );
-    if name == "Set-Cookie" { return size * nmemb; }
+    let version = line.slice_to(space);
+    let rest = line.slice_from(space + 1).trim_left();
 
-    let h: &mut HashMap<String,String> = unsafe { mem::transmute(user_data) };
-    h.insert(name.to_string(),value.to_string());
-    size * nmemb
+    let (status, reason) = match rest.find(' ') {
+        Some(t) => (rest.slice_to(t), rest.slice_from(t + 1).trim()),
+        None => (rest, "")
+    };
+
+    match from_str::<uint>(status) {
+        Some(code) => Some((version.to_string(), code, reason.to_string())),
+        None => None
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::hashmap::HashMap;
-    use request::Request;
+    use headers::Headers;
+    use request::{Request,GET,RedirectPolicy};
 
     #[test]
     fn test_basic_client() {
@@ -158,10 +812,10 @@ mod test {
         let client = HttpClient::new();
 
         let url = "http://api.4chan.org/pol/threads.json";
-        let mut headers = HashMap::new();
-        headers.insert(headers::request::ACCEPT.to_string(),"application/json".to_str());
+        let mut headers = Headers::new();
+        headers.add(headers::request::ACCEPT, "application/json");
 
-        let req = Request::new(url.to_string(),HashMap::new(),vec![]);
+        let req = Request::new(GET,url.to_string(),headers,vec![]);
 
         let resp_res = client.exec(&req);
 
@@ -169,8 +823,532 @@ mod test {
             Ok(response) => { 
                 assert!(!response.headers.is_empty());
                 assert!(!response.body.is_empty());
+                assert!(response.is_success());
             }
-            Err(msg) => { fail!("Error" + msg); }
+            Err(err) => { fail!("Error {}", err); }
         };
     }
+
+    #[test]
+    fn test_head_request() {
+        use request::HEAD;
+
+        let client = HttpClient::new();
+        let req = Request::new(HEAD,"http://www.google.com".to_string(),Headers::new(),vec![]);
+
+        match client.exec(&req) {
+            Ok(response) => {
+                assert!(!response.headers.is_empty());
+                assert!(response.body.is_empty());
+            }
+            Err(err) => { fail!("Error {}", err); }
+        };
+    }
+
+    #[test]
+    fn test_post_sends_body() {
+        use request::POST;
+        use std::str::from_utf8;
+
+        let client = HttpClient::new();
+        let body = Vec::from_slice(bytes!("name=rust_curl&nul=\x00end"));
+        let req = Request::new(POST,"http://httpbin.org/post".to_string(),Headers::new(),body);
+
+        match client.exec(&req) {
+            Ok(response) => {
+                assert!(response.is_success());
+                let echoed = from_utf8(response.body.as_slice()).unwrap();
+                assert!(echoed.contains("rust_curl"));
+                assert!(echoed.contains("\"Content-Length\": \"23\""));
+            }
+            Err(err) => { fail!("Error {}", err); }
+        };
+    }
+
+    #[test]
+    fn test_post_body_is_sent_again_after_a_307() {
+        use std::str::from_utf8;
+
+        let client = HttpClient::new();
+        let req = Request::post("http://httpbin.org/redirect-to?url=%2Fpost&status_code=307")
+            .body(Vec::from_slice(bytes!("name=rust_curl&nul=\x00end")));
+
+        let resp = client.exec(&req).unwrap();
+        assert!(resp.is_success());
+        assert_eq!(resp.url, "http://httpbin.org/post".to_string());
+
+        let echoed = from_utf8(resp.body.as_slice()).unwrap();
+        assert!(echoed.contains("rust_curl"));
+        assert!(echoed.contains("\"Content-Length\": \"23\""));
+    }
+
+    #[test]
+    fn test_delete_without_body() {
+        use proxy::ProxyConfig;
+        use std::ascii::StrAsciiExt;
+
+        // the stand-in proxy shows exactly what was sent
+        let (port, head) = proxy_stand_in("HTTP/1.1 204 No Content\r\n\r\n");
+        let client = HttpClientBuilder::new()
+            .proxy_config(ProxyConfig::new(format!("127.0.0.1:{}", port).as_slice()))
+            .build();
+
+        assert_eq!(client.exec(&Request::delete("http://example.invalid/item/1")).unwrap().status_code, 204);
+
+        let head = head.recv().as_slice().to_ascii_lower();
+        assert!(head.as_slice().starts_with("delete http://example.invalid/item/1 http/1.1\r\n"));
+        assert!(!head.as_slice().contains("content-type"));
+        assert!(!head.as_slice().contains("content-length"));
+    }
+
+    #[test]
+    fn test_cookies_are_kept_and_sent_back() {
+        use std::str::from_utf8;
+
+        // turned on through a clone, which shares the setting
+        let client = HttpClient::new();
+        client.clone().enable_cookies().unwrap();
+
+        let url = "http://httpbin.org/cookies/set?session=abc123".to_string();
+        let resp = client.exec(&Request::new(GET,url,Headers::new(),vec![])).unwrap();
+        assert!(resp.is_success());
+
+        let cookies = client.cookies().unwrap();
+        assert!(cookies.iter().any(|c| c.name.as_slice() == "session" && c.value.as_slice() == "abc123"));
+
+        let url = "http://httpbin.org/cookies".to_string();
+        let resp = client.exec(&Request::new(GET,url,Headers::new(),vec![])).unwrap();
+        assert!(from_utf8(resp.body.as_slice()).unwrap().contains("abc123"));
+
+        client.clear_cookies().unwrap();
+        assert!(client.cookies().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cookies_need_a_share() {
+        use curl::code;
+
+        // as if libcurl could not make the share handle
+        let client = HttpClient { share: None, ..HttpClient::new() };
+
+        assert!(client.enable_cookies().unwrap_err().code == code::CURLE_NOT_BUILT_IN);
+        assert!(client.add_cookie("Set-Cookie: sid=abc; Domain=example.com").is_err());
+        assert!(client.cookies().is_err());
+        assert!(client.clear_cookies().is_err());
+        assert!(client.set_cookie_jar(&Path::new("cookies.txt")).is_err());
+    }
+
+    #[test]
+    fn test_exec_to_writer() {
+        use std::io::MemWriter;
+
+        let client = HttpClient::new();
+        let req = Request::new(GET,"http://httpbin.org/bytes/4096".to_string(),Headers::new(),vec![]);
+
+        let mut writer = MemWriter::new();
+        let resp = client.exec_to(&req, &mut writer).unwrap();
+
+        assert!(resp.is_success());
+        assert!(resp.body.is_empty());
+        assert_eq!(writer.get_ref().len(), 4096);
+    }
+
+    #[test]
+    fn test_exec_to_failing_writer() {
+        use std::io::{IoResult, IoError, OtherIoError};
+        use curl::code;
+
+        struct FullDisk;
+
+        impl Writer for FullDisk {
+            fn write(&mut self, _buf: &[u8]) -> IoResult<()> {
+                Err(IoError { kind: OtherIoError, desc: "disk full", detail: None })
+            }
+        }
+
+        let client = HttpClient::new();
+        let req = Request::new(GET,"http://httpbin.org/bytes/4096".to_string(),Headers::new(),vec![]);
+
+        match client.exec_to(&req, &mut FullDisk) {
+            Ok(_) => { fail!("a failing writer should fail the request"); }
+            Err(err) => {
+                assert!(err.code == code::CURLE_WRITE_ERROR);
+                assert!(err.message.as_slice().contains("disk full"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_handles_are_reused() {
+        let client = HttpClient::new();
+        let req = Request::new(GET,"http://httpbin.org/get".to_string(),Headers::new(),vec![]);
+
+        assert!(client.exec(&req).unwrap().is_success());
+        assert!(client.exec(&req).unwrap().is_success());
+        assert_eq!(client.pool.lock().len(), 1);
+    }
+
+    #[test]
+    fn test_shared_between_tasks() {
+        let client = HttpClient::new();
+        let (tx, rx) = channel();
+
+        for _ in range(0u, 4) {
+            let (client, tx) = (client.clone(), tx.clone());
+            spawn(proc() {
+                let req = Request::new(GET,"http://httpbin.org/get".to_string(),Headers::new(),vec![]);
+                tx.send(client.exec(&req).map(|resp| resp.status_code));
+            });
+        }
+
+        for _ in range(0u, 4) {
+            assert_eq!(rx.recv().unwrap(), 200);
+        }
+        assert!(client.pool.lock().len() <= 4);
+    }
+
+    #[test]
+    fn test_builder_defaults_and_overrides() {
+        use std::str::from_utf8;
+        use headers;
+
+        let client = HttpClientBuilder::new()
+            .base_url("http://httpbin.org/")
+            .user_agent("rust_curl-test")
+            .header(headers::request::ACCEPT, "application/json")
+            .max_redirects(0)
+            .build();
+
+        let resp = client.exec(&Request::new(GET,"/headers".to_string(),Headers::new(),vec![])).unwrap();
+        let echoed = from_utf8(resp.body.as_slice()).unwrap().to_string();
+        assert!(echoed.as_slice().contains("rust_curl-test"));
+
+        let mut headers = Headers::new();
+        headers.add(headers::request::USER_AGENT, "overridden");
+        let resp = client.exec(&Request::new(GET,"/headers".to_string(),headers,vec![])).unwrap();
+        let echoed = from_utf8(resp.body.as_slice()).unwrap().to_string();
+        assert!(echoed.as_slice().contains("overridden"));
+        assert!(!echoed.as_slice().contains("rust_curl-test"));
+
+        // the client follows no redirects, this request follows one
+        let url = "/redirect/1".to_string();
+        assert!(client.exec(&Request::new(GET,url.clone(),Headers::new(),vec![])).unwrap().is_redirect());
+
+        let mut req = Request::new(GET,url,Headers::new(),vec![]);
+        req.settings.redirects = Some(RedirectPolicy::limit(1));
+        assert!(client.exec(&req).unwrap().is_success());
+    }
+
+    #[test]
+    fn test_timeout() {
+        let client = HttpClientBuilder::new().timeout(500).build();
+        let req = Request::new(GET,"http://httpbin.org/delay/3".to_string(),Headers::new(),vec![]);
+
+        match client.exec(&req) {
+            Ok(_) => { fail!("the request should have timed out"); }
+            Err(err) => { assert!(err.is_timeout()); }
+        }
+    }
+
+    /// Answers the first request made to a local port with `reply`, and
+    /// sends back the head of that request
+    fn proxy_stand_in(reply: &'static str) -> (u16, Receiver<String>) {
+        use std::io::{TcpListener,Listener,Acceptor,BufferedReader};
+
+        let listener = TcpListener::bind("127.0.0.1", 0).unwrap();
+        let port = listener.socket_name().unwrap().port;
+        let acceptor = listener.listen().unwrap();
+        let (tx, rx) = channel();
+
+        spawn(proc() {
+            let mut acceptor = acceptor;
+            let mut stream = acceptor.accept().unwrap();
+            let mut reader = BufferedReader::new(stream.clone());
+
+            let mut head = String::new();
+            loop {
+                let line = reader.read_line().unwrap();
+                if line.as_slice() == "\r\n" { break; }
+                head.push_str(line.as_slice());
+            }
+
+            stream.write(reply.as_bytes()).unwrap();
+            tx.send(head);
+        });
+
+        (port, rx)
+    }
+
+    #[test]
+    fn test_proxy() {
+        use proxy::{ProxyConfig,BasicAuth};
+        use std::str::from_utf8;
+
+        let (port, head) = proxy_stand_in("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+        let proxy = ProxyConfig::new(format!("127.0.0.1:{}", port).as_slice())
+            .credentials("ada", "s3cret")
+            .auth(BasicAuth);
+        let client = HttpClientBuilder::new().proxy_config(proxy).build();
+
+        let resp = client.exec(&Request::get("http://example.invalid/path")).unwrap();
+        assert_eq!(resp.status_code, 200);
+        assert_eq!(from_utf8(resp.body.as_slice()), Some("ok"));
+
+        let head = head.recv();
+        assert!(head.as_slice().starts_with("GET http://example.invalid/path HTTP/1.1\r\n"));
+        assert!(head.as_slice().contains("Proxy-Authorization: Basic YWRhOnMzY3JldA==\r\n"));
+    }
+
+    #[test]
+    fn test_proxy_tunnel() {
+        use proxy::ProxyConfig;
+
+        let (port, head) = proxy_stand_in("HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n");
+        let client = HttpClient::new();
+
+        // the proxy refuses the tunnel, so the request fails
+        let mut req = Request::get("http://example.invalid/path");
+        req.settings.proxy = Some(ProxyConfig::new(format!("http://127.0.0.1:{}", port).as_slice()).tunnel(true));
+        assert!(client.exec(&req).is_err());
+        assert!(head.recv().as_slice().starts_with("CONNECT example.invalid:80 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_builder_merges_tls() {
+        use tls::{TlsConfig,Tls12};
+
+        let builder = HttpClientBuilder::new()
+            .verify_tls(false)
+            .tls(TlsConfig::new().min_version(Tls12).ca_path(&Path::new("/etc/ssl/certs")))
+            .ca_bundle(&Path::new("/etc/ca.pem"));
+
+        let tls = builder.settings.tls.unwrap();
+        assert_eq!(tls.verify_peer, Some(false));
+        assert_eq!(tls.min_version, Some(Tls12));
+        assert_eq!(tls.ca_path, Some("/etc/ssl/certs".to_string()));
+        assert_eq!(tls.ca_bundle, Some("/etc/ca.pem".to_string()));
+    }
+
+    #[test]
+    fn test_tls_config() {
+        use tls::{TlsConfig,Tls12};
+
+        let client = HttpClientBuilder::new().tls(TlsConfig::new().min_version(Tls12)).build();
+        assert!(client.exec(&Request::get("https://expired.badssl.com/")).is_err());
+
+        // verification is turned off for this request only, the minimum version is kept
+        let mut req = Request::get("https://expired.badssl.com/");
+        req.settings.tls = Some(TlsConfig::new().verify(false));
+        assert!(client.exec(&req).unwrap().is_success());
+        assert!(client.exec(&Request::get("https://tls-v1-1.badssl.com:1011/")).is_err());
+
+        let pinned = TlsConfig::new().pinned_public_key("sha256//AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+        let mut req = Request::get("https://httpbin.org/get");
+        req.settings.tls = Some(pinned);
+        assert!(client.exec(&req).is_err());
+    }
+
+    #[test]
+    fn test_transfer_stats() {
+        let client = HttpClient::new();
+
+        let stats = client.exec(&Request::get("https://httpbin.org/get")).unwrap().stats;
+        assert!(stats.total_time > 0.0);
+        assert!(stats.ttfb() <= stats.total_time);
+        assert!(stats.tls() > 0.0);
+        assert!(stats.size_download > 0.0);
+        assert_eq!(stats.num_connects, 1);
+
+        let stats = client.exec(&Request::get("http://httpbin.org/redirect/1")).unwrap().stats;
+        assert_eq!(stats.redirect_count, 1);
+    }
+
+    #[test]
+    fn test_certificates() {
+        use tls::TlsConfig;
+
+        let client = HttpClientBuilder::new().tls(TlsConfig::new().cert_chain(true)).build();
+
+        // each request gets the chain, even though the connection could be reused
+        for _ in range(0u, 2) {
+            let certs = client.exec(&Request::get("https://httpbin.org/get")).unwrap().certificates;
+            assert!(certs.len() >= 2);
+            assert!(certs.get(0).subject.as_slice().contains("httpbin.org"));
+            assert_eq!(certs.get(0).issuer, certs.get(1).subject);
+            assert!(certs.get(0).pem.as_slice().starts_with("-----BEGIN CERTIFICATE-----"));
+            assert!(!certs.get(0).serial.is_empty());
+            assert!(certs.get(0).not_before.unwrap() < certs.get(0).not_after.unwrap());
+            assert!(!certs.get(0).expires_within(0));
+        }
+
+        assert!(HttpClient::new().exec(&Request::get("https://httpbin.org/get")).unwrap().certificates.is_empty());
+
+        // only the request asking for the chain skips the pooled connection
+        let client = HttpClient::new();
+        let plain = Request::get("https://httpbin.org/get");
+        let mut collecting = Request::get("https://httpbin.org/get");
+        collecting.settings.tls = Some(TlsConfig::new().cert_chain(true));
+
+        assert!(client.exec(&plain).unwrap().certificates.is_empty());
+        let res = client.exec(&collecting).unwrap();
+        assert_eq!(res.stats.num_connects, 1);
+        assert!(!res.certificates.is_empty());
+        assert_eq!(client.exec(&plain).unwrap().stats.num_connects, 0);
+    }
+
+    #[test]
+    fn test_redirect_history() {
+        use curl::code;
+        use curl::curl_ll::CURLPROTO_HTTPS;
+
+        let client = HttpClient::new();
+
+        let resp = client.exec(&Request::get("http://httpbin.org/redirect/2")).unwrap();
+        assert!(resp.is_success());
+        assert_eq!(resp.url, "http://httpbin.org/get".to_string());
+        assert_eq!(resp.redirects.len(), 2);
+        assert_eq!(resp.redirects.get(0).url, "http://httpbin.org/redirect/2".to_string());
+        assert_eq!(resp.redirects.get(0).status_code, 302);
+
+        let mut req = Request::get("http://httpbin.org/redirect/3");
+        req.settings.redirects = Some(RedirectPolicy::limit(1));
+        assert!(client.exec(&req).unwrap_err().code == code::CURLE_TOO_MANY_REDIRECTS);
+
+        // https only: the redirect to plain http is refused
+        let mut req = Request::get("https://httpbin.org/redirect-to?url=http%3A%2F%2Fhttpbin.org%2Fget");
+        req.settings.redirects = Some(RedirectPolicy { protocols: CURLPROTO_HTTPS, ..RedirectPolicy::new() });
+        assert!(client.exec(&req).is_err());
+    }
+
+    #[test]
+    fn test_redirect_keep_post() {
+        use std::str::from_utf8;
+
+        let client = HttpClient::new();
+        let url = "http://httpbin.org/redirect-to?url=%2Fpost&status_code=302";
+
+        // /post only answers POSTs, so a success shows the method was kept
+        let mut req = Request::post(url).body(Vec::from_slice(bytes!("name=rust_curl")));
+        req.settings.redirects = Some(RedirectPolicy { keep_post: true, ..RedirectPolicy::new() });
+        let resp = client.exec(&req).unwrap();
+        assert!(resp.is_success());
+        assert_eq!(resp.redirects.get(0).status_code, 302);
+        assert!(from_utf8(resp.body.as_slice()).unwrap().contains("rust_curl"));
+
+        // by default the POST turns into a GET, which /post refuses
+        let req = Request::post(url).body(Vec::from_slice(bytes!("name=rust_curl")));
+        assert_eq!(client.exec(&req).unwrap().status_code, 405);
+    }
+
+    #[test]
+    fn test_redirects() {
+        use super::redirects;
+        use response::Redirect;
+
+        let hops = vec![("http://a/".to_string(), 301), ("http://b/".to_string(), 100),
+                        ("http://b/".to_string(), 302), ("http://c/".to_string(), 304)];
+        assert_eq!(redirects(hops.as_slice()),
+                   vec![Redirect { url: "http://a/".to_string(), status_code: 301 },
+                        Redirect { url: "http://b/".to_string(), status_code: 302 }]);
+        assert_eq!(redirects([]), vec![]);
+    }
+
+    #[test]
+    fn test_content_decoding() {
+        use std::str::from_utf8;
+        use headers;
+
+        let client = HttpClient::new();
+        let resp = client.exec(&Request::get("http://httpbin.org/gzip")).unwrap();
+        assert_eq!(resp.content_encoding, Some("gzip".to_string()));
+        assert!(!resp.headers.contains(headers::response::CONTENT_ENCODING));
+        assert!(from_utf8(resp.body.as_slice()).unwrap().contains("\"gzipped\": true"));
+
+        let raw = HttpClientBuilder::new().decode_content(false).build();
+        let resp = raw.exec(&Request::get("http://httpbin.org/gzip")).unwrap();
+        assert_eq!(resp.content_encoding, None);
+        assert_eq!(resp.headers.get(headers::response::CONTENT_ENCODING), Some("gzip"));
+        assert!(resp.body.slice_to(2) == [0x1fu8, 0x8b].as_slice());
+    }
+
+    #[test]
+    fn test_strip_content_encoding() {
+        use super::strip_content_encoding;
+        use headers;
+
+        let mut headers = Headers::new();
+        headers.add(headers::response::CONTENT_ENCODING, "identity");
+        assert_eq!(strip_content_encoding(&mut headers), None);
+        assert!(headers.contains(headers::response::CONTENT_ENCODING));
+
+        headers.set(headers::response::CONTENT_ENCODING, "br");
+        headers.add(headers::response::CONTENT_LENGTH, "120");
+        assert_eq!(strip_content_encoding(&mut headers), Some("br".to_string()));
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn test_multipart_form() {
+        use std::str::from_utf8;
+        use std::io::MemReader;
+        use form::{MultipartForm,Part};
+
+        let client = HttpClient::new();
+        let stream = box MemReader::new(Vec::from_slice(bytes!("streamed body")));
+        let form = MultipartForm::new()
+            .text("title", "holiday")
+            .part(Part::bytes("thumb", vec![0x89u8, 0x50]).filename("thumb.png").content_type("image/png"))
+            .part(Part::reader("notes", stream, None).filename("notes.txt"));
+
+        let resp = client.exec(&Request::post("http://httpbin.org/post").form(form)).unwrap();
+        assert!(resp.is_success());
+
+        let echoed = from_utf8(resp.body.as_slice()).unwrap();
+        assert!(echoed.contains("\"title\": \"holiday\""));
+        assert!(echoed.contains("\"notes\": \"streamed body\""));
+        assert!(echoed.contains("multipart/form-data"));
+    }
+
+    #[test]
+    fn test_form_is_refused_on_get_and_next_to_a_body() {
+        use curl::code;
+        use form::MultipartForm;
+
+        let client = HttpClient::new();
+
+        let req = Request::get("http://example.invalid/").form(MultipartForm::new().text("a", "b"));
+        assert!(client.exec(&req).unwrap_err().code == code::CURLE_BAD_FUNCTION_ARGUMENT);
+
+        let req = Request::post("http://example.invalid/")
+            .body(vec![1u8])
+            .form(MultipartForm::new().text("a", "b"));
+        assert!(client.exec(&req).unwrap_err().code == code::CURLE_BAD_FUNCTION_ARGUMENT);
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        use super::resolve_url;
+
+        assert_eq!(resolve_url(None, "users"), "users".to_string());
+        assert_eq!(resolve_url(Some("http://a.com/v1/"), "/users"), "http://a.com/v1/users".to_string());
+        assert_eq!(resolve_url(Some("http://a.com/v1"), "users?id=1"), "http://a.com/v1/users?id=1".to_string());
+        assert_eq!(resolve_url(Some("http://a.com/v1"), "https://b.com/"), "https://b.com/".to_string());
+        assert_eq!(resolve_url(Some("http://a.com"), "/cb?next=http://x"), "http://a.com/cb?next=http://x".to_string());
+        assert_eq!(resolve_url(Some("http://a.com"), "svn+ssh://b.com/"), "svn+ssh://b.com/".to_string());
+        assert_eq!(resolve_url(Some("http://a.com"), "1a://b"), "http://a.com/1a://b".to_string());
+    }
+
+    #[test]
+    fn test_parse_status_line() {
+        use super::parse_status_line;
+
+        assert_eq!(parse_status_line("HTTP/1.1 200 OK\r\n"),
+                   Some(("HTTP/1.1".to_string(), 200, "OK".to_string())));
+        assert_eq!(parse_status_line("HTTP/1.0 404 Not Found\r\n"),
+                   Some(("HTTP/1.0".to_string(), 404, "Not Found".to_string())));
+        assert_eq!(parse_status_line("HTTP/2 204\r\n"),
+                   Some(("HTTP/2".to_string(), 204, "".to_string())));
+        assert_eq!(parse_status_line("Content-Type: text/html\r\n"), None);
+        assert_eq!(parse_status_line("\r\n"), None);
+    }
 }
@@ -0,0 +1,210 @@
+use std::ascii::StrAsciiExt;
+use std::os;
+
+use curl::curl_ll::{CURLPROXY_HTTP,CURLPROXY_HTTPS,CURLPROXY_SOCKS4,CURLPROXY_SOCKS4A,CURLPROXY_SOCKS5,
+                    CURLPROXY_SOCKS5_HOSTNAME,CURLAUTH_BASIC,CURLAUTH_DIGEST,CURLAUTH_NEGOTIATE,
+                    CURLAUTH_NTLM,CURLAUTH_ANY,CURLAUTH_ANYSAFE};
+
+/// The protocol spoken to a proxy
+#[deriving(Show, Clone, PartialEq)]
+pub enum ProxyKind {
+    HttpProxy,
+    /// An HTTP proxy reached over TLS
+    HttpsProxy,
+    Socks4Proxy,
+    /// SOCKS4 with the host name resolved by the proxy
+    Socks4aProxy,
+    Socks5Proxy,
+    /// SOCKS5 with the host name resolved by the proxy
+    Socks5HostnameProxy
+}
+
+impl ProxyKind {
+    /// The CURLPROXY_* value for CURLOPT_PROXYTYPE
+    pub fn to_curl(&self) -> int {
+        match *self {
+            HttpProxy => CURLPROXY_HTTP,
+            HttpsProxy => CURLPROXY_HTTPS,
+            Socks4Proxy => CURLPROXY_SOCKS4,
+            Socks4aProxy => CURLPROXY_SOCKS4A,
+            Socks5Proxy => CURLPROXY_SOCKS5,
+            Socks5HostnameProxy => CURLPROXY_SOCKS5_HOSTNAME
+        }
+    }
+}
+
+/// How to authenticate to an HTTP proxy
+#[deriving(Show, Clone, PartialEq)]
+pub enum AuthScheme {
+    BasicAuth,
+    DigestAuth,
+    NegotiateAuth,
+    NtlmAuth,
+    /// Whichever the proxy offers, picking the safest
+    AnyAuth,
+    /// Whichever the proxy offers, except those sending the password in the clear
+    AnySafeAuth
+}
+
+impl AuthScheme {
+    /// The CURLAUTH_* bits for CURLOPT_PROXYAUTH
+    pub fn to_curl(&self) -> int {
+        match *self {
+            BasicAuth => CURLAUTH_BASIC,
+            DigestAuth => CURLAUTH_DIGEST,
+            NegotiateAuth => CURLAUTH_NEGOTIATE,
+            NtlmAuth => CURLAUTH_NTLM,
+            AnyAuth => CURLAUTH_ANY,
+            AnySafeAuth => CURLAUTH_ANYSAFE
+        }
+    }
+}
+
+/// A proxy to send requests through. Given client-wide with
+/// `HttpClientBuilder::proxy_config` or per request in `Request::settings`.
+///
+/// # Example
+/// ~~~ {.rust}
+/// let proxy = ProxyConfig::new("socks5h://gateway.local:1080")
+///     .credentials("me", "s3cret")
+///     .no_proxy("localhost,.internal.example.com");
+///
+/// let client = HttpClientBuilder::new().proxy_config(proxy).build();
+/// ~~~
+#[deriving(Show, Clone, PartialEq)]
+pub struct ProxyConfig {
+    /// The proxy, e.g. "http://proxy.local:3128" or "socks5://10.0.0.1".
+    /// Empty to connect directly.
+    pub url: String,
+    /// The protocol, for URLs without a scheme; HTTP if neither says
+    pub kind: Option<ProxyKind>,
+    /// User name and password for the proxy
+    pub credentials: Option<(String, String)>,
+    /// How to send the credentials to an HTTP proxy, Basic by default
+    pub auth: Option<AuthScheme>,
+    /// Tunnel through an HTTP proxy with CONNECT, even for plain http URLs
+    pub tunnel: Option<bool>,
+    /// Hosts to reach directly, separated by commas; "*" for all
+    pub no_proxy: Option<String>
+}
+
+impl ProxyConfig {
+    /// Send requests through the proxy at `url`
+    pub fn new(url: &str) -> ProxyConfig {
+        ProxyConfig {
+            url: url.to_string(),
+            kind: None,
+            credentials: None,
+            auth: None,
+            tunnel: None,
+            no_proxy: None
+        }
+    }
+
+    /// Connect directly, ignoring the client's proxy and the environment
+    pub fn direct() -> ProxyConfig {
+        ProxyConfig::new("")
+    }
+
+    /// The proxy the environment gives for `url`: `https_proxy` for https
+    /// URLs, `http_proxy` for http ones, else `all_proxy`, each also read
+    /// in upper case except `HTTP_PROXY`, which CGI servers fill in from a
+    /// request header. `no_proxy` is honoured too. None without a proxy.
+    pub fn from_env(url: &str) -> Option<ProxyConfig> {
+        ProxyConfig::from_vars(url, |name| os::getenv(name))
+    }
+
+    fn from_vars(url: &str, mut getenv: |&str| -> Option<String>) -> Option<ProxyConfig> {
+        let scheme = match url.find_str("://") {
+            Some(i) => url.slice_to(i).to_ascii_lower(),
+            None => "http".to_string()
+        };
+        let names = match scheme.as_slice() {
+            "https" => vec!["https_proxy", "HTTPS_PROXY", "all_proxy", "ALL_PROXY"],
+            "http" => vec!["http_proxy", "all_proxy", "ALL_PROXY"],
+            _ => vec!["all_proxy", "ALL_PROXY"]
+        };
+
+        let url = match first_var(names.as_slice(), &mut getenv) {
+            Some(url) => url,
+            None => { return None; }
+        };
+        let mut proxy = ProxyConfig::new(url.as_slice());
+        proxy.no_proxy = first_var(["no_proxy", "NO_PROXY"], &mut getenv);
+        Some(proxy)
+    }
+
+    /// Speak `kind` to the proxy, for URLs without a scheme
+    pub fn kind(mut self, kind: ProxyKind) -> ProxyConfig {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Log in to the proxy with this user name and password
+    pub fn credentials(mut self, user: &str, password: &str) -> ProxyConfig {
+        self.credentials = Some((user.to_string(), password.to_string()));
+        self
+    }
+
+    /// Send the credentials as `scheme` says
+    pub fn auth(mut self, scheme: AuthScheme) -> ProxyConfig {
+        self.auth = Some(scheme);
+        self
+    }
+
+    /// Whether to tunnel every request with CONNECT
+    pub fn tunnel(mut self, tunnel: bool) -> ProxyConfig {
+        self.tunnel = Some(tunnel);
+        self
+    }
+
+    /// Reach these hosts directly, e.g. "localhost,.example.com"
+    pub fn no_proxy(mut self, hosts: &str) -> ProxyConfig {
+        self.no_proxy = Some(hosts.to_string());
+        self
+    }
+}
+
+/// The trimmed value of the first of `names` that is set and not blank
+fn first_var(names: &[&str], getenv: &mut |&str| -> Option<String>) -> Option<String> {
+    for name in names.iter() {
+        match (*getenv)(*name) {
+            Some(ref value) if !value.as_slice().trim().is_empty() => {
+                return Some(value.as_slice().trim().to_string());
+            }
+            _ => { ; }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from_vars(url: &str, vars: &[(&str, &str)]) -> Option<ProxyConfig> {
+        let vars: HashMap<String, String> = vars.iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        ProxyConfig::from_vars(url, |name| vars.find_equiv(&name).map(|v| v.clone()))
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let vars = [("http_proxy", "http://plain:3128"), ("HTTPS_PROXY", "http://secure:3128"),
+                    ("no_proxy", "localhost,.internal")];
+
+        let proxy = from_vars("http://example.com/", vars).unwrap();
+        assert_eq!(proxy.url, "http://plain:3128".to_string());
+        assert_eq!(proxy.no_proxy, Some("localhost,.internal".to_string()));
+        assert_eq!(from_vars("HTTPS://example.com/", vars).unwrap().url, "http://secure:3128".to_string());
+        assert_eq!(from_vars("ftp://example.com/", vars), None);
+
+        // HTTP_PROXY is ignored, empty values are unset
+        let vars = [("HTTP_PROXY", "http://cgi:80"), ("all_proxy", ""), ("ALL_PROXY", "socks5://all:1080")];
+        assert_eq!(from_vars("http://example.com/", vars).unwrap().url, "socks5://all:1080".to_string());
+        assert_eq!(from_vars("http://example.com/", []), None);
+    }
+}
@@ -1,36 +1,348 @@
+use headers;
 use headers::Headers;
+use urlencode;
+use form::MultipartForm;
+use tls::TlsConfig;
+use proxy::ProxyConfig;
+use curl::curl_ll::{CURLPROTO_HTTP,CURLPROTO_HTTPS};
 
 /// Represents HTTP request methods
+#[deriving(Show, Clone, PartialEq)]
 pub enum Method {
     GET,
-    POST
+    HEAD,
+    POST,
+    PUT,
+    PATCH,
+    DELETE,
+    OPTIONS,
+    TRACE,
+    /// Any other verb, sent as-is (e.g. "PROPFIND")
+    Custom(String)
+}
+
+impl Method {
+    /// The verb as it appears on the request line
+    pub fn as_str<'a>(&'a self) -> &'a str {
+        match *self {
+            GET => "GET",
+            HEAD => "HEAD",
+            POST => "POST",
+            PUT => "PUT",
+            PATCH => "PATCH",
+            DELETE => "DELETE",
+            OPTIONS => "OPTIONS",
+            TRACE => "TRACE",
+            Custom(ref verb) => verb.as_slice()
+        }
+    }
+}
+
+/// Transfer settings, given client-wide through `HttpClientBuilder` or
+/// for a single request through `Request::settings`. A setting left as
+/// None on a request falls back to the client's.
+#[deriving(Show, Clone, PartialEq)]
+pub struct Settings {
+    /// Limit on the whole transfer, in milliseconds
+    pub timeout_ms: Option<uint>,
+    /// Limit on connecting, in milliseconds
+    pub connect_timeout_ms: Option<uint>,
+    /// The proxy to send requests through
+    pub proxy: Option<ProxyConfig>,
+    /// Without a `proxy`: true to take it from the http_proxy and related
+    /// environment variables (see `ProxyConfig::from_env`), false to
+    /// connect directly. Unset leaves it to libcurl, which reads some of them.
+    pub proxy_from_env: Option<bool>,
+    /// Certificate checks and client credentials; merged field by field
+    pub tls: Option<TlsConfig>,
+    /// Which redirects to follow, see `RedirectPolicy::new` for the default
+    pub redirects: Option<RedirectPolicy>,
+    /// User name and password for the server
+    pub auth: Option<(String, String)>,
+    /// Whether to ask for a compressed body and decode it. On unless set
+    /// to false, for callers who want the raw bytes.
+    pub decode_content: Option<bool>
+}
+
+impl Settings {
+    /// Settings with nothing set
+    pub fn new() -> Settings {
+        Settings {
+            timeout_ms: None,
+            connect_timeout_ms: None,
+            proxy: None,
+            proxy_from_env: None,
+            tls: None,
+            redirects: None,
+            auth: None,
+            decode_content: None
+        }
+    }
+
+    /// These settings, with anything unset taken from `defaults`
+    pub fn or(&self, defaults: &Settings) -> Settings {
+        Settings {
+            timeout_ms: self.timeout_ms.or(defaults.timeout_ms),
+            connect_timeout_ms: self.connect_timeout_ms.or(defaults.connect_timeout_ms),
+            proxy: self.proxy.clone().or(defaults.proxy.clone()),
+            proxy_from_env: self.proxy_from_env.or(defaults.proxy_from_env),
+            tls: match (&self.tls, &defaults.tls) {
+                (&Some(ref tls), &Some(ref default)) => Some(tls.or(default)),
+                (tls, default) => tls.clone().or(default.clone())
+            },
+            redirects: self.redirects.clone().or(defaults.redirects.clone()),
+            auth: self.auth.clone().or(defaults.auth.clone()),
+            decode_content: self.decode_content.or(defaults.decode_content)
+        }
+    }
+}
+
+/// How redirects are followed
+#[deriving(Show, Clone, PartialEq)]
+pub struct RedirectPolicy {
+    /// Redirects to follow before failing, 0 to follow none
+    pub max: uint,
+    /// Keep POSTing after a 301, 302 or 303, instead of switching to GET
+    pub keep_post: bool,
+    /// Protocols a redirect may lead to, as CURLPROTO_* bits
+    pub protocols: int,
+    /// Send credentials on to hosts other than the one first requested
+    pub send_auth_to_other_hosts: bool
+}
+
+impl RedirectPolicy {
+    /// Follow up to 20 redirects to http and https URLs, switching POSTs to
+    /// GET like browsers do, and keep credentials to the first host
+    pub fn new() -> RedirectPolicy {
+        RedirectPolicy {
+            max: 20,
+            keep_post: false,
+            protocols: CURLPROTO_HTTP | CURLPROTO_HTTPS,
+            send_auth_to_other_hosts: false
+        }
+    }
+
+    /// Don't follow redirects, return them as responses
+    pub fn none() -> RedirectPolicy {
+        RedirectPolicy::limit(0)
+    }
+
+    /// The default policy, following at most `max` redirects
+    pub fn limit(max: uint) -> RedirectPolicy {
+        RedirectPolicy { max: max, ..RedirectPolicy::new() }
+    }
 }
 
 /// The HTTP request struct
 #[deriving(Show)]
 pub struct Request {
+    pub method: Method,
     pub url: String,
     pub headers: Headers,
-    body: Vec<u8>
+    pub body: Vec<u8>,
+    /// A multipart/form-data body, sent in place of `body`, which must be empty
+    pub form: Option<MultipartForm>,
+    /// Overrides for the client's settings
+    pub settings: Settings
 }
 
 impl Request {
     /// Create a new Request
     /// # Arguments
+    /// * `method` -    the HTTP method of the request
     /// * `url` -   the URL of the request, properly escaped
     /// * `headers` - the HTTP headers you choose to use
-    /// * `body` - the body of the request
+    /// * `body` - the body of the request, ignored for GET and HEAD
     /// # Example
     /// ~~~ {.rust}
-    /// use std::hashmap::HashMap;
+    /// use headers::Headers;
     ///
-    /// let mut headers = HashMap::new();
-    /// headers.insert(headers::request::ACCEPT.to_string(),~"application/json");
+    /// let mut headers = Headers::new();
+    /// headers.add(headers::request::ACCEPT, "application/json");
     ///
-    /// let req = Request::new(url.to_string(),headers,~[]);
+    /// let req = Request::new(GET,url.to_string(),headers,~[]);
+    /// ~~~
+    pub fn new(method: Method, url: String, headers: Headers, body: Vec<u8>) -> Request {
+        Request {method: method, url: url, headers: headers, body: body, form: None, settings: Settings::new()}
+    }
+
+    /// Start building a GET request for `url`
+    /// # Example
+    /// ~~~ {.rust}
+    /// // http://example.com/search/rust%20curl?q=a%20b&page=2
+    /// let req = Request::get("http://example.com/search")
+    ///     .segment("rust curl")
+    ///     .query("q", "a b")
+    ///     .query("page", "2")
+    ///     .header(headers::request::ACCEPT, "text/html");
+    /// ~~~
+    pub fn get(url: &str) -> Request {
+        Request::new(GET, url.to_string(), Headers::new(), vec![])
+    }
+
+    /// Start building a HEAD request for `url`
+    pub fn head(url: &str) -> Request {
+        Request::new(HEAD, url.to_string(), Headers::new(), vec![])
+    }
+
+    /// Start building a POST request for `url`
+    pub fn post(url: &str) -> Request {
+        Request::new(POST, url.to_string(), Headers::new(), vec![])
+    }
+
+    /// Start building a PUT request for `url`
+    pub fn put(url: &str) -> Request {
+        Request::new(PUT, url.to_string(), Headers::new(), vec![])
+    }
+
+    /// Start building a PATCH request for `url`
+    pub fn patch(url: &str) -> Request {
+        Request::new(PATCH, url.to_string(), Headers::new(), vec![])
+    }
+
+    /// Start building a DELETE request for `url`
+    pub fn delete(url: &str) -> Request {
+        Request::new(DELETE, url.to_string(), Headers::new(), vec![])
+    }
+
+    /// Append a query parameter, percent-encoded, after any query already
+    /// in the URL. Parameters keep their order and keys may repeat.
+    pub fn query(mut self, key: &str, value: &str) -> Request {
+        let url = {
+            let (rest, fragment) = split_at_any(self.url.as_slice(), ['#']);
+            let sep = match rest.find('?') {
+                None => "?",
+                Some(_) if rest.ends_with("?") || rest.ends_with("&") => "",
+                Some(_) => "&"
+            };
+            format!("{}{}{}={}{}", rest, sep, urlencode::encode(key), urlencode::encode(value), fragment)
+        };
+
+        self.url = url;
+        self
+    }
+
+    /// Append a path segment, percent-encoded so that slashes and other
+    /// delimiters in it are kept as data
+    pub fn segment(mut self, segment: &str) -> Request {
+        let url = {
+            let (path, tail) = split_at_any(self.url.as_slice(), ['?', '#']);
+            let sep = if path.ends_with("/") { "" } else { "/" };
+            format!("{}{}{}{}", path, sep, urlencode::encode(segment), tail)
+        };
+
+        self.url = url;
+        self
+    }
+
+    /// Add a header
+    pub fn header(mut self, name: &str, value: &str) -> Request {
+        self.headers.add(name, value);
+        self
+    }
+
+    /// Set the body
+    pub fn body(mut self, body: Vec<u8>) -> Request {
+        self.body = body;
+        self
+    }
+
+    /// Send `pairs` as an application/x-www-form-urlencoded body, the way
+    /// a browser submits a simple form, and set the Content-Type to match
+    /// # Example
+    /// ~~~ {.rust}
+    /// let req = Request::post("http://example.com/login")
+    ///     .form_urlencoded([("user", "ada"), ("password", "s3cret & more")]);
     /// ~~~
-    pub fn new(url: String, headers: Headers, body: Vec<u8>) -> Request {
-        Request {url: url, headers: headers, body: body}
+    pub fn form_urlencoded(mut self, pairs: &[(&str, &str)]) -> Request {
+        self.body = urlencode::encode_form(pairs).into_bytes();
+        self.headers.set(headers::request::CONTENT_TYPE, urlencode::FORM_CONTENT_TYPE);
+        self
+    }
+
+    /// Send `form` as a multipart/form-data body. The request fails if it
+    /// is a GET or HEAD, or also has a body.
+    pub fn form(mut self, form: MultipartForm) -> Request {
+        self.form = Some(form);
+        self
     }
 }
 
+/// Splits `s` before the first of `chars`, or returns it whole
+fn split_at_any<'a>(s: &'a str, chars: &[char]) -> (&'a str, &'a str) {
+    match s.find(chars) {
+        Some(i) => (s.slice_to(i), s.slice_from(i)),
+        None => (s, "")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_settings_or() {
+        use tls::TlsConfig;
+        use proxy::ProxyConfig;
+
+        let mut defaults = Settings::new();
+        defaults.timeout_ms = Some(5000);
+        defaults.proxy = Some(ProxyConfig::new("http://proxy.local:3128"));
+        defaults.tls = Some(TlsConfig::new().ca_bundle(&Path::new("/etc/ca.pem")));
+
+        let mut settings = Settings::new();
+        settings.timeout_ms = Some(100);
+        settings.tls = Some(TlsConfig::new().verify(false));
+
+        let merged = settings.or(&defaults);
+        assert_eq!(merged.timeout_ms, Some(100));
+        assert_eq!(merged.proxy, Some(ProxyConfig::new("http://proxy.local:3128")));
+        let tls = merged.tls.unwrap();
+        assert_eq!(tls.verify_peer, Some(false));
+        assert_eq!(tls.ca_bundle, Some("/etc/ca.pem".to_string()));
+        assert_eq!(merged.redirects, None);
+    }
+
+    #[test]
+    fn test_builder() {
+        use headers;
+
+        let req = Request::post("http://example.com/api")
+            .segment("users")
+            .segment("a/b c")
+            .query("q", "a&b")
+            .query("tag", "x")
+            .query("tag", "y")
+            .header(headers::request::ACCEPT, "application/json")
+            .body(Vec::from_slice(bytes!("data")));
+
+        assert_eq!(req.method, POST);
+        assert_eq!(req.url, "http://example.com/api/users/a%2Fb%20c?q=a%26b&tag=x&tag=y".to_string());
+        assert_eq!(req.headers.get(headers::request::ACCEPT), Some("application/json"));
+        assert_eq!(req.body, Vec::from_slice(bytes!("data")));
+    }
+
+    #[test]
+    fn test_query_merges_with_url() {
+        assert_eq!(Request::get("http://a.com/?x=1").query("y", "2").url, "http://a.com/?x=1&y=2".to_string());
+        assert_eq!(Request::get("http://a.com/?").query("y", "2").url, "http://a.com/?y=2".to_string());
+        assert_eq!(Request::get("http://a.com/p#top").query("y", "2").url, "http://a.com/p?y=2#top".to_string());
+        assert_eq!(Request::get("http://a.com/p?x=1#top").segment("q").url, "http://a.com/p/q?x=1#top".to_string());
+    }
+
+    #[test]
+    fn test_form_urlencoded() {
+        use headers;
+        use std::str::from_utf8;
+        use urlencode;
+
+        let req = Request::post("http://example.com/login")
+            .form_urlencoded([("user", "ada"), ("note", "a b&c")]);
+
+        assert_eq!(req.headers.get(headers::request::CONTENT_TYPE), Some(urlencode::FORM_CONTENT_TYPE));
+        let body = from_utf8(req.body.as_slice()).unwrap();
+        assert_eq!(body, "user=ada&note=a+b%26c");
+        assert_eq!(urlencode::decode_form(body),
+                   vec![("user".to_string(), "ada".to_string()), ("note".to_string(), "a b&c".to_string())]);
+    }
+}
@@ -1,15 +1,126 @@
 use headers::Headers;
+use headers;
+use cookie::Cookie;
+use tls::Certificate;
 
 /// Represents an HTTP response
 #[deriving(Show)]
 pub struct Response {
+    pub status_code: uint,
+    pub reason: String,
+    pub http_version: String,
     pub headers: Headers,
-    pub body: Vec<u8>
+    pub body: Vec<u8>,
+    /// The URL the response came from, after any redirects
+    pub url: String,
+    /// The redirects followed to get here, in order
+    pub redirects: Vec<Redirect>,
+    /// The Content-Encoding the body was decoded from, e.g. "gzip". The
+    /// Content-Encoding header is removed, along with the Content-Length
+    /// of the encoded body.
+    pub content_encoding: Option<String>,
+    /// Timings and sizes of the transfer that produced this response
+    pub stats: TransferStats,
+    /// The server's certificate chain, its own certificate first. Only
+    /// collected when `TlsConfig::cert_chain` is on.
+    pub certificates: Vec<Certificate>
+}
+
+/// A redirect followed on the way to a response
+#[deriving(Show, Clone, PartialEq)]
+pub struct Redirect {
+    /// The URL that answered with the redirect
+    pub url: String,
+    /// Its status code, e.g. 301
+    pub status_code: uint
+}
+
+/// Timings and sizes of a transfer, as reported by curl. Times are in
+/// seconds from the start of the transfer, so each one includes the
+/// phases before it. Use the helper methods for the length of a phase.
+#[deriving(Show, Clone, PartialEq)]
+pub struct TransferStats {
+    /// Until the name was resolved
+    pub namelookup_time: f64,
+    /// Until the TCP connection to the host or proxy was made
+    pub connect_time: f64,
+    /// Until the TLS handshake was done, 0 for plain transfers
+    pub appconnect_time: f64,
+    /// Until the request was about to be sent
+    pub pretransfer_time: f64,
+    /// Until the first byte of the response arrived
+    pub starttransfer_time: f64,
+    /// The whole transfer, redirects included
+    pub total_time: f64,
+    /// Spent following redirects before the final transfer began
+    pub redirect_time: f64,
+    /// Redirects followed
+    pub redirect_count: uint,
+    /// Bytes downloaded, headers excluded
+    pub size_download: f64,
+    /// Bytes uploaded
+    pub size_upload: f64,
+    /// Average download speed, in bytes per second
+    pub speed_download: f64,
+    /// Average upload speed, in bytes per second
+    pub speed_upload: f64,
+    /// New connections made for the transfer; 0 if one was reused
+    pub num_connects: uint
+}
+
+impl TransferStats {
+    /// Stats with every value zeroed
+    pub fn new() -> TransferStats {
+        TransferStats {
+            namelookup_time: 0.0,
+            connect_time: 0.0,
+            appconnect_time: 0.0,
+            pretransfer_time: 0.0,
+            starttransfer_time: 0.0,
+            total_time: 0.0,
+            redirect_time: 0.0,
+            redirect_count: 0,
+            size_download: 0.0,
+            size_upload: 0.0,
+            speed_download: 0.0,
+            speed_upload: 0.0,
+            num_connects: 0
+        }
+    }
+
+    /// Time spent resolving the name
+    pub fn dns(&self) -> f64 {
+        self.namelookup_time
+    }
+
+    /// Time spent making the TCP connection
+    pub fn connect(&self) -> f64 {
+        phase(self.namelookup_time, self.connect_time)
+    }
+
+    /// Time spent on the TLS handshake, 0 for plain transfers
+    pub fn tls(&self) -> f64 {
+        if self.appconnect_time == 0.0 { 0.0 } else { phase(self.connect_time, self.appconnect_time) }
+    }
+
+    /// Time to first byte: from the start until the response began to arrive
+    pub fn ttfb(&self) -> f64 {
+        self.starttransfer_time
+    }
+}
+
+/// The length of a phase that ran from `start` until `end`; curl leaves
+/// the times of phases it skipped, like connecting on a reused connection, at 0
+fn phase(start: f64, end: f64) -> f64 {
+    if end > start { end - start } else { 0.0 }
 }
 
 impl Response {
     /// Creates a new response struct
     /// # Arguments
+    /// * `status_code` -   the numeric HTTP status, e.g. 404
+    /// * `reason` -    the reason phrase of the status line, e.g. "Not Found"
+    /// * `http_version` -  the protocol of the status line, e.g. "HTTP/1.1"
     /// * `headers` -   the HTTP headers from the response
     /// * `body` -  the body of the HTTP response
     /// # Example
@@ -20,9 +131,9 @@ impl Response {
     ///
     /// let url = "http://api.4chan.org/pol/threads.json";
     ///
-    /// let req = Request::new(url.to_string(),HashMap::new(),~[]);
+    /// let req = Request::new(GET,url.to_string(),Headers::new(),vec![]);
     ///
-    /// let resp_res: Result<Response,~str> = client.exec(&req);
+    /// let resp_res: CurlResult<Response> = client.exec(&req);
     ///
     /// match resp_res {
     ///     code::CURLE_OK => {
@@ -34,7 +145,44 @@ impl Response {
     ///     Err(msg) => { fail!("Error" + msg); }
     /// };
     /// ~~~
-    pub fn new(headers: Headers, body: Vec<u8>) -> Response {
-        Response {headers: headers, body: body}
+    pub fn new(status_code: uint, reason: String, http_version: String,
+               headers: Headers, body: Vec<u8>) -> Response {
+        Response {status_code: status_code, reason: reason, http_version: http_version,
+                  headers: headers, body: body, url: String::new(), redirects: vec![],
+                  content_encoding: None, stats: TransferStats::new(),
+                  certificates: vec![]}
+    }
+
+    /// The cookies set by the response, parsed from every Set-Cookie header.
+    /// Headers that cannot be parsed are skipped.
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.headers.get_all(headers::response::SET_COOKIE).iter()
+            .filter_map(|h| Cookie::parse(*h))
+            .collect()
+    }
+
+    /// True for 1xx status codes
+    pub fn is_informational(&self) -> bool {
+        self.status_code >= 100 && self.status_code < 200
+    }
+
+    /// True for 2xx status codes
+    pub fn is_success(&self) -> bool {
+        self.status_code >= 200 && self.status_code < 300
+    }
+
+    /// True for 3xx status codes
+    pub fn is_redirect(&self) -> bool {
+        self.status_code >= 300 && self.status_code < 400
+    }
+
+    /// True for 4xx status codes
+    pub fn is_client_error(&self) -> bool {
+        self.status_code >= 400 && self.status_code < 500
+    }
+
+    /// True for 5xx status codes
+    pub fn is_server_error(&self) -> bool {
+        self.status_code >= 500 && self.status_code < 600
     }
 }
@@ -0,0 +1,325 @@
+use time;
+
+use curl::curl_ll::{CURL_SSLVERSION_TLSv1_0,CURL_SSLVERSION_TLSv1_1,CURL_SSLVERSION_TLSv1_2,
+                    CURL_SSLVERSION_TLSv1_3};
+
+/// TLS protocol versions
+#[deriving(Show, Clone, PartialEq)]
+pub enum TlsVersion {
+    Tls10,
+    Tls11,
+    Tls12,
+    Tls13
+}
+
+impl TlsVersion {
+    /// The CURL_SSLVERSION_* value for CURLOPT_SSLVERSION
+    pub fn to_curl(&self) -> int {
+        match *self {
+            Tls10 => CURL_SSLVERSION_TLSv1_0,
+            Tls11 => CURL_SSLVERSION_TLSv1_1,
+            Tls12 => CURL_SSLVERSION_TLSv1_2,
+            Tls13 => CURL_SSLVERSION_TLSv1_3
+        }
+    }
+}
+
+/// How TLS connections are verified and which credentials they present.
+/// Given client-wide with `HttpClientBuilder::tls` or per request in
+/// `Request::settings`; a setting left as None on a request falls back
+/// to the client's, then to libcurl's default.
+///
+/// # Example
+/// ~~~ {.rust}
+/// let tls = TlsConfig::new()
+///     .ca_bundle(&Path::new("/etc/internal/ca.pem"))
+///     .client_cert(&Path::new("me.pem"), &Path::new("me.key"), Some("passphrase"))
+///     .min_version(Tls12);
+///
+/// let client = HttpClientBuilder::new().tls(tls).build();
+/// ~~~
+#[deriving(Show, Clone, PartialEq)]
+pub struct TlsConfig {
+    /// Whether to check the server's certificate chain
+    pub verify_peer: Option<bool>,
+    /// Whether to check that the certificate is for the host connected to
+    pub verify_host: Option<bool>,
+    /// File holding the CA certificates to verify servers against
+    pub ca_bundle: Option<String>,
+    /// Directory of CA certificates to verify servers against
+    pub ca_path: Option<String>,
+    /// The client certificate for mutual TLS
+    pub client_cert: Option<String>,
+    /// The format of the client certificate: "PEM" (the default), "DER" or "P12"
+    pub client_cert_type: Option<String>,
+    /// The private key of the client certificate
+    pub client_key: Option<String>,
+    /// The passphrase of the private key
+    pub key_password: Option<String>,
+    /// The lowest TLS version to accept
+    pub min_version: Option<TlsVersion>,
+    /// Ciphers to allow, in the TLS library's syntax, e.g. "ECDHE-RSA-AES128-GCM-SHA256"
+    pub ciphers: Option<String>,
+    /// File holding certificate revocation lists
+    pub crl_file: Option<String>,
+    /// The certificate the server's certificate must be issued by
+    pub issuer_cert: Option<String>,
+    /// The server's public key, as a file or "sha256//<base64 hash>" pins
+    pub pinned_public_key: Option<String>,
+    /// Collect the server's certificate chain into `Response::certificates`.
+    /// Every request then makes a new connection, as a reused one has no
+    /// handshake to take the chain from.
+    pub cert_chain: Option<bool>
+}
+
+/// A certificate the server presented, as reported by the TLS library
+#[deriving(Show, Clone, PartialEq)]
+pub struct Certificate {
+    /// The distinguished name of the certificate's owner
+    pub subject: String,
+    /// The distinguished name of the certificate's issuer
+    pub issuer: String,
+    /// The serial number, as written by the TLS library (usually hex)
+    pub serial: String,
+    /// Start of the validity period, in seconds since the epoch
+    pub not_before: Option<i64>,
+    /// End of the validity period, in seconds since the epoch
+    pub not_after: Option<i64>,
+    /// The certificate in PEM form
+    pub pem: String,
+    /// Every field reported, in order, e.g. ("Signature Algorithm", "sha256WithRSAEncryption")
+    pub fields: Vec<(String, String)>
+}
+
+impl TlsConfig {
+    /// A config with nothing set
+    pub fn new() -> TlsConfig {
+        TlsConfig {
+            verify_peer: None,
+            verify_host: None,
+            ca_bundle: None,
+            ca_path: None,
+            client_cert: None,
+            client_cert_type: None,
+            client_key: None,
+            key_password: None,
+            min_version: None,
+            ciphers: None,
+            crl_file: None,
+            issuer_cert: None,
+            pinned_public_key: None,
+            cert_chain: None
+        }
+    }
+
+    /// This config, with anything unset taken from `defaults`
+    pub fn or(&self, defaults: &TlsConfig) -> TlsConfig {
+        TlsConfig {
+            verify_peer: self.verify_peer.or(defaults.verify_peer),
+            verify_host: self.verify_host.or(defaults.verify_host),
+            ca_bundle: self.ca_bundle.clone().or(defaults.ca_bundle.clone()),
+            ca_path: self.ca_path.clone().or(defaults.ca_path.clone()),
+            client_cert: self.client_cert.clone().or(defaults.client_cert.clone()),
+            client_cert_type: self.client_cert_type.clone().or(defaults.client_cert_type.clone()),
+            client_key: self.client_key.clone().or(defaults.client_key.clone()),
+            key_password: self.key_password.clone().or(defaults.key_password.clone()),
+            min_version: self.min_version.clone().or(defaults.min_version.clone()),
+            ciphers: self.ciphers.clone().or(defaults.ciphers.clone()),
+            crl_file: self.crl_file.clone().or(defaults.crl_file.clone()),
+            issuer_cert: self.issuer_cert.clone().or(defaults.issuer_cert.clone()),
+            pinned_public_key: self.pinned_public_key.clone().or(defaults.pinned_public_key.clone()),
+            cert_chain: self.cert_chain.or(defaults.cert_chain)
+        }
+    }
+
+    /// Check both the certificate chain and the host name, or neither
+    pub fn verify(mut self, verify: bool) -> TlsConfig {
+        self.verify_peer = Some(verify);
+        self.verify_host = Some(verify);
+        self
+    }
+
+    /// Verify servers against the CA certificates in `path`
+    pub fn ca_bundle(mut self, path: &Path) -> TlsConfig {
+        self.ca_bundle = Some(path_str(path));
+        self
+    }
+
+    /// Verify servers against the CA certificates in the directory `path`
+    pub fn ca_path(mut self, path: &Path) -> TlsConfig {
+        self.ca_path = Some(path_str(path));
+        self
+    }
+
+    /// Present the certificate `cert`, with the private key `key` and its passphrase
+    pub fn client_cert(mut self, cert: &Path, key: &Path, password: Option<&str>) -> TlsConfig {
+        self.client_cert = Some(path_str(cert));
+        self.client_key = Some(path_str(key));
+        self.key_password = password.map(|p| p.to_string());
+        self
+    }
+
+    /// The format of the client certificate: "PEM", "DER" or "P12"
+    pub fn client_cert_type(mut self, cert_type: &str) -> TlsConfig {
+        self.client_cert_type = Some(cert_type.to_string());
+        self
+    }
+
+    /// Refuse TLS versions older than `version`
+    pub fn min_version(mut self, version: TlsVersion) -> TlsConfig {
+        self.min_version = Some(version);
+        self
+    }
+
+    /// Allow only these ciphers
+    pub fn ciphers(mut self, ciphers: &str) -> TlsConfig {
+        self.ciphers = Some(ciphers.to_string());
+        self
+    }
+
+    /// Check server certificates against the revocation lists in `path`
+    pub fn crl_file(mut self, path: &Path) -> TlsConfig {
+        self.crl_file = Some(path_str(path));
+        self
+    }
+
+    /// Collect the server's certificate chain into `Response::certificates`.
+    /// Each request collecting it opens a new connection, with a full TLS
+    /// handshake, instead of reusing one; on a client's defaults that is
+    /// every request, so prefer setting it on the requests that need it.
+    pub fn cert_chain(mut self, collect: bool) -> TlsConfig {
+        self.cert_chain = Some(collect);
+        self
+    }
+
+    /// Accept only server certificates issued by the certificate in `path`
+    pub fn issuer_cert(mut self, path: &Path) -> TlsConfig {
+        self.issuer_cert = Some(path_str(path));
+        self
+    }
+
+    /// Accept only servers with this public key
+    pub fn pinned_public_key(mut self, pin: &str) -> TlsConfig {
+        self.pinned_public_key = Some(pin.to_string());
+        self
+    }
+}
+
+impl Certificate {
+    /// Builds a certificate from the "Name:value" lines CURLINFO_CERTINFO
+    /// gives for it. Lines without a colon continue the previous value, as
+    /// some TLS libraries split the PEM over several lines.
+    pub fn from_lines(lines: &[String]) -> Certificate {
+        let mut fields = vec![];
+        let mut current: Option<(String, String)> = None;
+
+        for line in lines.iter() {
+            let line = line.as_slice();
+            match line.find(':') {
+                Some(i) => {
+                    fields.extend(current.take().move_iter());
+                    current = Some((line.slice_to(i).to_string(), line.slice_from(i + 1).to_string()));
+                }
+                None => match current {
+                    Some((_, ref mut value)) => {
+                        value.push_char('\n');
+                        value.push_str(line);
+                    }
+                    None => { ; }
+                }
+            }
+        }
+        fields.extend(current.move_iter());
+
+        Certificate {
+            subject: cert_field(fields.as_slice(), "Subject"),
+            issuer: cert_field(fields.as_slice(), "Issuer"),
+            serial: cert_field(fields.as_slice(), "Serial Number"),
+            not_before: parse_cert_date(cert_field(fields.as_slice(), "Start date").as_slice()),
+            not_after: parse_cert_date(cert_field(fields.as_slice(), "Expire date").as_slice()),
+            pem: cert_field(fields.as_slice(), "Cert"),
+            fields: fields.clone()
+        }
+    }
+
+    /// Whether the certificate's validity ends within `secs` seconds from now
+    pub fn expires_within(&self, secs: i64) -> bool {
+        match self.not_after {
+            Some(t) => t - time::get_time().sec <= secs,
+            None => false
+        }
+    }
+}
+
+/// The trimmed value of the first field called `name`, or ""
+fn cert_field(fields: &[(String, String)], name: &str) -> String {
+    fields.iter()
+        .find(|&&(ref n, _)| n.as_slice() == name)
+        .map(|&(_, ref v)| v.as_slice().trim().to_string())
+        .unwrap_or(String::new())
+}
+
+/// Parse a certificate date such as "Jan  1 00:00:00 2025 GMT" (OpenSSL)
+/// or "2025-01-01 00:00:00 GMT" (GnuTLS and others) into seconds since the epoch
+fn parse_cert_date(date: &str) -> Option<i64> {
+    let words: Vec<&str> = date.words().collect();
+    let date = words.connect(" ");
+    let formats = ["%b %d %H:%M:%S %Y GMT", "%Y-%m-%d %H:%M:%S GMT"];
+
+    for format in formats.iter() {
+        match time::strptime(date.as_slice(), *format) {
+            Ok(tm) => { return Some(tm.to_timespec().sec); }
+            Err(_) => { ; }
+        }
+    }
+
+    None
+}
+
+fn path_str(path: &Path) -> String {
+    format!("{}", path.display())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_or() {
+        let client = TlsConfig::new()
+            .ca_bundle(&Path::new("/etc/ca.pem"))
+            .client_cert(&Path::new("a.pem"), &Path::new("a.key"), Some("secret"))
+            .min_version(Tls12);
+        let req = TlsConfig::new().min_version(Tls13).verify(false);
+
+        let merged = req.or(&client);
+        assert_eq!(merged.min_version, Some(Tls13));
+        assert_eq!(merged.verify_peer, Some(false));
+        assert_eq!(merged.ca_bundle, Some("/etc/ca.pem".to_string()));
+        assert_eq!(merged.key_password, Some("secret".to_string()));
+        assert_eq!(merged.ciphers, None);
+    }
+
+    #[test]
+    fn test_certificate_from_lines() {
+        let lines: Vec<String> = ["Subject:CN = example.com",
+                                  "Issuer:C = US, O = Let's Encrypt, CN = R3",
+                                  "Serial Number:04:af:21",
+                                  "Start date:Jan  1 00:00:00 2025 GMT",
+                                  "Expire date:Apr  1 00:00:00 2025 GMT",
+                                  "Cert:-----BEGIN CERTIFICATE-----",
+                                  "MIIB",
+                                  "-----END CERTIFICATE-----"]
+            .iter().map(|l| l.to_string()).collect();
+
+        let cert = Certificate::from_lines(lines.as_slice());
+        assert_eq!(cert.subject, "CN = example.com".to_string());
+        assert_eq!(cert.issuer, "C = US, O = Let's Encrypt, CN = R3".to_string());
+        assert_eq!(cert.serial, "04:af:21".to_string());
+        assert_eq!(cert.not_before, Some(1735689600));
+        assert_eq!(cert.not_after, Some(1743465600));
+        assert_eq!(cert.pem, "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----".to_string());
+        assert_eq!(cert.fields.len(), 6);
+        assert!(cert.expires_within(0));
+    }
+}
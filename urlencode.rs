@@ -0,0 +1,170 @@
+/// Percent-encodes a URL component (a query key or value, or a path
+/// segment). Everything but the unreserved characters of RFC 3986 is
+/// escaped, so the result can't change the structure of the URL.
+/// # Example
+/// ~~~ {.rust}
+/// assert_eq!(urlencode::encode("a b&c/d"), "a%20b%26c%2Fd".to_string());
+/// ~~~
+pub fn encode(s: &str) -> String {
+    let mut out = String::new();
+
+    for &b in s.as_bytes().iter() {
+        if is_unreserved(b) {
+            out.push_char(b as char);
+        } else {
+            out.push_str(format!("%{:02X}", b).as_slice());
+        }
+    }
+
+    out
+}
+
+/// Encodes key/value pairs into a query string, keeping their order and
+/// any repeated keys
+/// # Example
+/// ~~~ {.rust}
+/// let q = urlencode::encode_query([("tag", "a"), ("tag", "b c")]);
+/// assert_eq!(q, "tag=a&tag=b%20c".to_string());
+/// ~~~
+pub fn encode_query(pairs: &[(&str, &str)]) -> String {
+    let encoded: Vec<String> = pairs.iter()
+        .map(|&(k, v)| format!("{}={}", encode(k), encode(v)))
+        .collect();
+
+    encoded.connect("&")
+}
+
+/// The Content-Type of a body made by `encode_form`
+pub static FORM_CONTENT_TYPE: &'static str = "application/x-www-form-urlencoded";
+
+/// Encodes key/value pairs into an application/x-www-form-urlencoded
+/// body, the format browsers submit forms in. It is the same as a query
+/// string, except that spaces become "+".
+/// # Example
+/// ~~~ {.rust}
+/// let body = urlencode::encode_form([("name", "Ada Lovelace"), ("lang", "en")]);
+/// assert_eq!(body, "name=Ada+Lovelace&lang=en".to_string());
+/// ~~~
+pub fn encode_form(pairs: &[(&str, &str)]) -> String {
+    let encoded: Vec<String> = pairs.iter()
+        .map(|&(k, v)| format!("{}={}", encode_form_component(k), encode_form_component(v)))
+        .collect();
+
+    encoded.connect("&")
+}
+
+/// Decodes percent-escapes in `s`. Malformed escapes are kept as they are
+/// and invalid UTF-8 is replaced.
+/// # Example
+/// ~~~ {.rust}
+/// assert_eq!(urlencode::decode("a%20b"), "a b".to_string());
+/// ~~~
+pub fn decode(s: &str) -> String {
+    use std::str::from_utf8_lossy;
+
+    let bytes = s.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = if bytes[i] == '%' as u8 && i + 2 < bytes.len() {
+            match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                (Some(hi), Some(lo)) => Some(hi * 16 + lo),
+                _ => None
+            }
+        } else {
+            None
+        };
+
+        match escaped {
+            Some(b) => { out.push(b); i += 3; }
+            None => { out.push(bytes[i]); i += 1; }
+        }
+    }
+
+    from_utf8_lossy(out.as_slice()).into_string()
+}
+
+/// Decodes an application/x-www-form-urlencoded body back into its
+/// key/value pairs, in order
+/// # Example
+/// ~~~ {.rust}
+/// let pairs = urlencode::decode_form("name=Ada+Lovelace&lang=en");
+/// assert_eq!(pairs, vec![("name".to_string(), "Ada Lovelace".to_string()),
+///                        ("lang".to_string(), "en".to_string())]);
+/// ~~~
+pub fn decode_form(body: &str) -> Vec<(String, String)> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let plain = pair.replace("+", " ");
+            match plain.as_slice().find('=') {
+                Some(eq) => (decode(plain.as_slice().slice_to(eq)), decode(plain.as_slice().slice_from(eq + 1))),
+                None => (decode(plain.as_slice()), String::new())
+            }
+        })
+        .collect()
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+fn encode_form_component(s: &str) -> String {
+    encode(s).replace("%20", "+")
+}
+
+/// ALPHA / DIGIT / "-" / "." / "_" / "~"
+fn is_unreserved(b: u8) -> bool {
+    let c = b as char;
+    b < 0x80 && (c.is_alphanumeric() || "-._~".contains_char(c))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("abcXYZ019-._~"), "abcXYZ019-._~".to_string());
+        assert_eq!(encode("a b"), "a%20b".to_string());
+        assert_eq!(encode("a+b=c&d?e#f/g%"), "a%2Bb%3Dc%26d%3Fe%23f%2Fg%25".to_string());
+        assert_eq!(encode("żółw"), "%C5%BC%C3%B3%C5%82w".to_string());
+        assert_eq!(encode(""), "".to_string());
+    }
+
+    #[test]
+    fn test_encode_query() {
+        assert_eq!(encode_query([("q", "a b"), ("page", "2"), ("q", "c")]),
+                   "q=a%20b&page=2&q=c".to_string());
+        assert_eq!(encode_query([]), "".to_string());
+    }
+
+    #[test]
+    fn test_encode_form() {
+        assert_eq!(encode_form([("name", "Ada Lovelace"), ("q", "1+1=2&x")]),
+                   "name=Ada+Lovelace&q=1%2B1%3D2%26x".to_string());
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("a%20b%2Fc"), "a b/c".to_string());
+        assert_eq!(decode("%C5%BC%C3%B3%C5%82w"), "żółw".to_string());
+        assert_eq!(decode("100%"), "100%".to_string());
+        assert_eq!(decode("%zz%4"), "%zz%4".to_string());
+    }
+
+    #[test]
+    fn test_decode_form() {
+        let pairs = [("name", "Ada Lovelace"), ("q", "1+1=2&x"), ("empty", ""), ("name", "żółw")];
+        let decoded = decode_form(encode_form(pairs).as_slice());
+
+        let expected: Vec<(String, String)> = pairs.iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(decoded, expected);
+
+        assert_eq!(decode_form("flag&&a=b"),
+                   vec![("flag".to_string(), "".to_string()), ("a".to_string(), "b".to_string())]);
+    }
+}